@@ -0,0 +1,55 @@
+//! Native perft runner, to compare `ChessBoard` move generation against reference engines.
+//!
+//! Usage: `cargo run --release --bin perft -- <depth> [fen]`
+
+use std::{env, process, time::Instant};
+
+use chess::{
+    perft::{perft_divide_stats, PerftStats},
+    Game,
+};
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some(depth) = args.first().and_then(|depth| depth.parse::<u32>().ok()) else {
+        eprintln!("Usage: perft <depth> [fen]");
+        process::exit(1);
+    };
+    // The FEN may be passed quoted or as separate arguments
+    let fen = if args.len() > 1 {
+        args[1..].join(" ")
+    } else {
+        START_FEN.to_string()
+    };
+
+    let mut game = match Game::from_fen(&fen) {
+        Ok(game) => game,
+        Err(error) => {
+            eprintln!("Invalid FEN: {error}");
+            process::exit(1);
+        }
+    };
+    let start = Instant::now();
+
+    let mut divide = perft_divide_stats(&mut game, depth);
+    divide.sort_by_key(|(mv, _)| mv.to_uci());
+    let mut stats = PerftStats::default();
+    for (mv, move_stats) in &divide {
+        println!("{}: {}", mv.to_uci(), move_stats.nodes);
+        stats += *move_stats;
+    }
+
+    let elapsed = start.elapsed();
+
+    println!();
+    println!("Nodes:       {}", stats.nodes);
+    println!("Captures:    {}", stats.captures);
+    println!("En passants: {}", stats.en_passants);
+    println!("Castles:     {}", stats.castles);
+    println!("Promotions:  {}", stats.promotions);
+    println!("Checks:      {}", stats.checks);
+    println!("Checkmates:  {}", stats.checkmates);
+    println!("Time:        {:.3}s", elapsed.as_secs_f64());
+}
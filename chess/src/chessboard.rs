@@ -6,8 +6,8 @@ use crate::{
     magic::{magic_index, make_table, BISHOP_MAGICS, ROOK_MAGICS},
     queen_attacks_on_the_fly, rook_attacks_on_the_fly,
     zobrist::update_castle_hash,
-    Bitboard, ChessError, ChessResponse, Color, Game, MoveData, MoveType, Piece, NOT_A_FILE,
-    NOT_H_FILE,
+    Bitboard, CastleType, ChessError, ChessResponse, Color, FenError, Game, MoveData, MoveList,
+    MoveType, Piece, Undo, LIGHT_SQUARES, NOT_A_FILE, NOT_H_FILE,
 };
 use async_graphql::SimpleObject;
 use serde::{Deserialize, Serialize};
@@ -77,66 +77,84 @@ impl ChessBoard {
         self.en_passant = 0x00;
     }
 
-    /// Generates a ChessBoard from a FEN string
+    /// Generates a ChessBoard from a FEN string. Only the piece placement is required, unknown
+    /// pieces are skipped and missing castling or en passant fields are left empty. Use
+    /// `Game::from_fen` to validate a FEN.
     pub fn with_fen(fen: &str) -> Self {
         let mut board = ChessBoard::default();
 
         let parts: Vec<&str> = fen.split_whitespace().collect();
-        let piece_placement = parts[0];
+        let piece_placement = parts.first().unwrap_or(&"");
         let castling_rights = parts.get(2).unwrap_or(&"-");
         let en_passant = parts.get(3).unwrap_or(&"-");
 
-        // Parse the piece placement
-        for (rank_idx, rank) in piece_placement.split('/').enumerate() {
+        for (rank_idx, rank) in piece_placement.split('/').take(8).enumerate() {
             let mut file_idx = 0;
             for c in rank.chars() {
-                let square = 1u64 << (63 - (rank_idx * 8 + file_idx));
-
-                match c {
-                    'P' => board.wP |= square,
-                    'N' => board.wN |= square,
-                    'B' => board.wB |= square,
-                    'R' => board.wR |= square,
-                    'Q' => board.wQ |= square,
-                    'K' => board.wK |= square,
-                    'p' => board.bP |= square,
-                    'n' => board.bN |= square,
-                    'b' => board.bB |= square,
-                    'r' => board.bR |= square,
-                    'q' => board.bQ |= square,
-                    'k' => board.bK |= square,
-                    '1'..='8' => {
-                        let empty_squares = c.to_digit(10).unwrap() as usize;
-                        file_idx += empty_squares - 1;
-                    }
-                    _ => {}
+                if let Some(empty_squares) = c.to_digit(10) {
+                    file_idx += empty_squares as usize;
+                    continue;
+                }
+
+                let color = if c.is_ascii_uppercase() {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                if let Some(piece) = Piece::from_letter(c, color).filter(|_| file_idx < 8) {
+                    *board.get_mut_board(&piece) |= 1u64 << ((7 - rank_idx) * 8 + file_idx);
                 }
                 file_idx += 1;
             }
         }
 
-        // Parse castling rights
-        board.castling_rights[0] = castling_rights.contains('K');
-        board.castling_rights[1] = castling_rights.contains('Q');
-        board.castling_rights[2] = castling_rights.contains('k');
-        board.castling_rights[3] = castling_rights.contains('q');
-
-        // Parse en passant
-        if *en_passant != "-" {
-            let en_passant_square = match en_passant.chars().nth(0) {
-                Some(file) => en_passant.chars().nth(1).map(|rank| {
-                    (rank.to_digit(10).unwrap() as u64 - 1) * 8 + (file as u64 - 'a' as u64)
-                }),
-                None => None,
-            };
-            if let Some(square) = en_passant_square {
-                board.en_passant = 1u64 << square;
-            }
+        board.castling_rights = ['K', 'Q', 'k', 'q'].map(|c| castling_rights.contains(c));
+        if let Ok(square) = en_passant.parse::<Square>() {
+            board.en_passant = 1u64 << square as u64;
         }
 
         board
     }
 
+    /// Generates a ChessBoard from the piece placement field of a FEN string
+    pub fn from_placement(placement: &str) -> std::result::Result<Self, FenError> {
+        let mut board = ChessBoard::default();
+
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::RankCount(ranks.len()));
+        }
+
+        for (rank_idx, rank) in ranks.iter().enumerate() {
+            let rank_number = 8 - rank_idx as u8;
+            let mut file_idx = 0;
+            for c in rank.chars() {
+                if let Some(empty_squares) = c.to_digit(10).filter(|n| (1..=8).contains(n)) {
+                    file_idx += empty_squares as usize;
+                    continue;
+                }
+
+                let color = if c.is_ascii_uppercase() {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                let piece = Piece::from_letter(c, color).ok_or(FenError::InvalidPiece(c))?;
+                if file_idx >= 8 {
+                    return Err(FenError::InvalidRank(rank_number));
+                }
+                *board.get_mut_board(&piece) |= 1u64 << ((7 - rank_idx) * 8 + file_idx);
+                file_idx += 1;
+            }
+
+            if file_idx != 8 {
+                return Err(FenError::InvalidRank(rank_number));
+            }
+        }
+
+        Ok(board)
+    }
+
     /// Helper function to extract moves from a bitboard
     pub fn extract_moves(&self, bitboard: u64) -> Vec<Square> {
         let mut moves = Vec::new();
@@ -389,10 +407,10 @@ impl ChessBoard {
     /// A function to revoke the castling right, when rook is moved for a player
     pub fn revoke_castling_rights(&mut self, color: Color, rook_position: Square) {
         match (color, rook_position) {
-            (Color::White, Square::A1) => self.castling_rights[0] = false,
-            (Color::White, Square::H1) => self.castling_rights[1] = false,
-            (Color::Black, Square::A8) => self.castling_rights[2] = false,
-            (Color::Black, Square::H8) => self.castling_rights[3] = false,
+            (Color::White, Square::H1) => self.castling_rights[0] = false,
+            (Color::White, Square::A1) => self.castling_rights[1] = false,
+            (Color::Black, Square::H8) => self.castling_rights[2] = false,
+            (Color::Black, Square::A8) => self.castling_rights[3] = false,
             _ => {}
         }
     }
@@ -411,19 +429,6 @@ impl ChessBoard {
         }
     }
 
-    /// A function to create capture string
-    pub fn create_capture_string(from: &str, to: &str) -> String {
-        // Extract the file from each square (the first character)
-        let from_file = &from[0..1];
-        let to_file = &to[0..1];
-
-        // Extract the rank from the 'to' square (the second character)
-        let to_rank = &to[1..2];
-
-        // Combine them into the desired format: "cxb4"
-        format!("{}x{}{}", from_file, to_file, to_rank)
-    }
-
     /// A function to get the mutable bitboard for a piece
     pub fn get_mut_board(&mut self, piece: &Piece) -> &mut BitBoard {
         match piece {
@@ -443,7 +448,7 @@ impl ChessBoard {
     }
 
     /// A function to get the bitboard of a piece as immutable reference
-    pub fn get_board(&mut self, piece: &Piece) -> &BitBoard {
+    pub fn get_board(&self, piece: &Piece) -> &BitBoard {
         match piece {
             Piece::WhitePawn => &self.wP,
             Piece::WhiteKnight => &self.wN,
@@ -460,131 +465,75 @@ impl ChessBoard {
         }
     }
 
-    ///A function to generate FEN string using bitboard
-    pub fn to_fen(
-        &self,
-        active_player: &Color,
-        halfmove_count: &u32,
-        fullmove_count: &u32,
-    ) -> String {
-        let bitboards = [
-            self.wP, self.wN, self.wB, self.wR, self.wQ, self.wK, self.bP, self.bN, self.bB,
-            self.bR, self.bQ, self.bK,
-        ];
-        let pieces = ['P', 'N', 'B', 'R', 'Q', 'K', 'p', 'n', 'b', 'r', 'q', 'k'];
-
-        let mut fen = String::with_capacity(100);
+    /// A function to generate the piece placement field of a FEN string
+    pub fn fen_placement(&self) -> String {
+        let mut placement = String::with_capacity(72);
 
         for rank in (0..8).rev() {
-            // Iterate over ranks 7 to 0
             let mut empty_squares = 0;
 
             for file in 0..8 {
-                let square = rank * 8 + file;
-                let mut piece_found = false;
-
-                for (i, &bitboard) in bitboards.iter().enumerate() {
-                    if (bitboard & (1u64 << square)) != 0 {
+                match self.get_piece_at(Square::usize_to_square(rank * 8 + file)) {
+                    Some(piece) => {
                         if empty_squares > 0 {
-                            fen.push_str(&empty_squares.to_string());
+                            placement.push_str(&empty_squares.to_string());
                             empty_squares = 0;
                         }
-                        fen.push(pieces[i]);
-                        piece_found = true;
-                        break;
+                        placement.push(match piece.color() {
+                            Color::White => piece.letter(),
+                            Color::Black => piece.letter().to_ascii_lowercase(),
+                        });
                     }
-                }
-
-                if !piece_found {
-                    empty_squares += 1;
+                    None => empty_squares += 1,
                 }
             }
 
             if empty_squares > 0 {
-                fen.push_str(&empty_squares.to_string());
+                placement.push_str(&empty_squares.to_string());
             }
-
             if rank > 0 {
-                fen.push('/');
-            }
-        }
-
-        fen.push(' '); // just to have a whitespace
-
-        //fen.push_str(
-        //    &active_player
-        //        .to_string()
-        //        .chars()
-        //        .next()
-        //        .unwrap_or_default()
-        //        .to_string(),
-        //);
-
-        fen.push(
-            active_player
-                .convert_to_string()
-                .chars()
-                .next()
-                .unwrap_or_default(),
-        );
-
-        // Add placeholder values for the rest of the FEN string
-        // castling rights for K(0)Q(1)k(2)q(3)
-        // Castling rights for K (White Kingside), Q (White Queenside),
-        // k (Black Kingside), q (Black Queenside)
-        let mut castling_rights_str = String::from(" ");
-
-        if !self.in_check(Color::White) {
-            if self.castling_rights[0] {
-                castling_rights_str.push('K');
-            }
-            if self.castling_rights[1] {
-                castling_rights_str.push('Q');
+                placement.push('/');
             }
         }
 
-        if !self.in_check(Color::Black) {
-            if self.castling_rights[2] {
-                castling_rights_str.push('k');
-            }
-            if self.castling_rights[3] {
-                castling_rights_str.push('q');
-            }
-        }
-
-        // If there are no castling rights, add "-" to indicate no castling is allowed
-        if castling_rights_str.is_empty() {
-            castling_rights_str.push('-');
-        }
-
-        fen.push_str(&castling_rights_str);
+        placement
+    }
 
-        if self.en_passant != 0 {
-            let en_passant_square = self.en_passant.trailing_zeros();
-            let square = Square::usize_to_string(en_passant_square as usize);
-            fen.push(' '); // just to have a whitespace
-            fen.push_str(&square);
+    /// Returns true if some sequence of legal moves lets the given color checkmate, which decides a
+    /// flag fall. A lone knight or bishops on one square color only mate against a king hemmed in
+    /// by its own pieces, so the opponent's material counts too
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        let [pawns, knights, bishops, rooks, queens] = self.material(color);
+        if pawns | rooks | queens != 0 {
+            return true;
+        }
+
+        let [opponent_pawns, opponent_knights, opponent_bishops, opponent_rooks, opponent_queens] =
+            self.material(color.opposite());
+        let blockers = opponent_pawns | opponent_knights | opponent_rooks | opponent_queens;
+        let other_color = if bishops & LIGHT_SQUARES == 0 {
+            LIGHT_SQUARES
+        } else if bishops & !LIGHT_SQUARES == 0 {
+            !LIGHT_SQUARES
         } else {
-            fen.push_str(" -");
-        }
-
-        fen.push(' '); // just to have a whitespace
-
-        fen.push_str(&halfmove_count.to_string());
-
-        fen.push(' '); // just to have a whitespace
-
-        fen.push_str(&fullmove_count.to_string());
+            // bishops on both square colors
+            return true;
+        };
 
-        if self.in_check(Color::White) {
-            fen.push_str(";wK");
+        match (knights.count_ones(), bishops) {
+            (0, 0) => false,
+            (0, _) => blockers | (opponent_bishops & other_color) != 0,
+            (1, 0) => blockers | opponent_bishops != 0,
+            _ => true,
         }
+    }
 
-        if self.in_check(Color::Black) {
-            fen.push_str(";bK");
+    /// The pawns, knights, bishops, rooks and queens of the given color
+    fn material(&self, color: Color) -> [BitBoard; 5] {
+        match color {
+            Color::White => [self.wP, self.wN, self.wB, self.wR, self.wQ],
+            Color::Black => [self.bP, self.bN, self.bB, self.bR, self.bQ],
         }
-
-        fen
     }
 
     /// Returns true if the king of the given color is in check
@@ -653,16 +602,6 @@ impl ChessBoard {
         self.bP | self.bN | self.bB | self.bR | self.bQ | self.bK
     }
 
-    /// Caputures a piece on the board
-    pub fn capture_piece(&mut self, to: Square, piece: &Piece) -> Result<()> {
-        let c_board = self.get_mut_board(piece);
-        if *c_board & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidPiece);
-        }
-        Self::clear(to, c_board);
-        Ok(())
-    }
-
     /// Sets a piece on the board
     pub fn set(square: Square, board: &mut BitBoard) {
         *board |= 1u64 << square as usize;
@@ -673,403 +612,429 @@ impl ChessBoard {
         *board &= !(1u64 << square as usize);
     }
 
-    /// A function to undo a move on the board
-    pub fn undo_move(&mut self, mv: &MoveData) -> Result<()> {
-        let from = mv.from;
-        let to = mv.to;
-        let piece = mv.piece;
-
-        Self::clear(to, self.get_mut_board(&piece));
-        Self::set(from, self.get_mut_board(&piece));
+    /// Plays a move on the board, moving the castling rook, removing the en passant victim,
+    /// placing the promoted piece and updating castling rights and the en passant square.
+    /// Returns the captured piece, if any. The move is assumed to be legal.
+    pub fn apply_move(&mut self, mv: &MoveData) -> Option<Piece> {
+        let color = mv.piece.color();
 
-        // Restore the captured piece, if any
-        if let MoveType::Capture(captured_piece) = mv.move_type {
-            Self::set(to, self.get_mut_board(&captured_piece));
+        let captured_piece = match mv.move_type {
+            MoveType::Castle(_) => None,
+            MoveType::EnPassant => Some(mv.piece.opp_piece()),
+            _ => self.get_piece_at(mv.to),
+        };
+        if let Some(captured_piece) = captured_piece {
+            let square = Self::captured_square(mv);
+            Self::clear(square, self.get_mut_board(&captured_piece));
         }
-        Ok(())
-    }
-
-    /// A function to add a piece to the board
-    pub fn add_piece(&mut self, at: Square, piece: Piece, promoted_to: Piece) -> Result<()> {
-        Self::clear(at, self.get_mut_board(&piece));
-        Self::set(at, self.get_mut_board(&promoted_to));
-        Ok(())
-    }
-
-    /// Moves a piece on the board, while checking if the king is in check
-    pub fn move_piece(&mut self, from: Square, to: Square, piece: &Piece) -> Result<()> {
-        let color = piece.color();
-        let board = self.get_board(piece);
 
-        // Check if the piece is at the 'from' square
-        if *board & (1u64 << from as usize) == 0 {
-            return Err(ChessError::InvalidMove);
-        }
+        let placed = match mv.move_type {
+            MoveType::Promotion(promoted) => promoted,
+            _ => mv.piece,
+        };
+        Self::clear(mv.from, self.get_mut_board(&mv.piece));
+        Self::set(mv.to, self.get_mut_board(&placed));
 
-        // if the player is already in_check, does making the move get king out of check?, if not don't move.
-        if self.in_check(color) {
-            Self::clear(from, self.get_mut_board(piece));
-            Self::set(to, self.get_mut_board(piece));
-            if self.in_check(color) {
-                Self::clear(to, self.get_mut_board(piece));
-                Self::set(from, self.get_mut_board(piece));
-                return Err(ChessError::InvalidMove);
-            }
+        if let MoveType::Castle(castle_type) = mv.move_type {
+            let (rook_from, rook_to) = Self::castle_rook_squares(color, castle_type);
+            let rook = Piece::from_letter('R', color).expect("Invalid piece");
+            Self::clear(rook_from, self.get_mut_board(&rook));
+            Self::set(rook_to, self.get_mut_board(&rook));
         }
 
-        // if the player is not in_check, remove the piece and check if the player is in_check, if not set the piece at new square.
-        if !self.in_check(color) {
-            Self::clear(from, self.get_mut_board(piece));
-            if self.in_check(color) {
-                Self::set(from, self.get_mut_board(piece));
-                return Err(ChessError::InvalidMove);
-            }
-            Self::set(to, self.get_mut_board(piece));
+        // a double pawn push sets the en passant square, every other move clears it
+        self.reset_enpassant();
+        if mv.piece.is_pawn() && (mv.to as usize).abs_diff(mv.from as usize) == 16 {
+            self.en_passant = 1u64 << ((mv.from as usize + mv.to as usize) / 2);
         }
 
-        // reset the enpassant square at the end of a move. if the move was not an en_passant
-        // capture, reset. if it was an en_passant capture then `self.en_passant == 0`
-        if self.en_passant != 0 {
-            self.reset_enpassant();
+        // moving the king or a rook, or losing a rook, revokes castling rights
+        if mv.piece.is_king() {
+            self.update_castling_rights(color);
         }
+        self.revoke_castling_rights(color, mv.from);
+        self.revoke_castling_rights(color.opposite(), mv.to);
 
-        Ok(())
+        captured_piece
     }
 
-    /** ---------------------------------------- Piece Move Logic ----------------------------------------- */
+    /// A function to undo a move on the board, restoring the castling rook, the captured piece
+    /// (including an en passant victim), the pawn of a promotion, castling rights and the en
+    /// passant square
+    pub fn undo_move(&mut self, undo: &Undo) -> Result<()> {
+        let mv = &undo.move_data;
+        let color = mv.piece.color();
 
-    /// Moves a white pawn
-    pub fn wP_moves(&mut self, from: Square, to: Square, piece: &Piece) -> Result<()> {
-        let sq = from as usize + 8_usize;
-        if to as usize == from as usize + 16 {
-            // Ensure the square directly in front is unoccupied
-            if self.all_pieces() & (1u64 << sq) != 0 {
-                return Err(ChessError::InvalidMove);
-            }
-            self.en_passant = 1u64 << sq;
-        }
-        if WHITE_PMOVES[from as usize] & (1u64 << to as usize) == 0 {
+        let placed = match mv.move_type {
+            MoveType::Promotion(promoted) => promoted,
+            _ => mv.piece,
+        };
+        if *self.get_board(&placed) & (1u64 << mv.to as usize) == 0 {
             return Err(ChessError::InvalidMove);
         }
-        self.move_piece(from, to, piece)
-    }
 
-    /// Moves a black pawn
-    pub fn bP_moves(&mut self, from: Square, to: Square, piece: &Piece) -> Result<()> {
-        let sq = from as usize - 8_usize;
-        if to as usize == from as usize - 16 {
-            // Ensure the square directly in front is unoccupied
-            if self.all_pieces() & (1u64 << sq) != 0 {
-                return Err(ChessError::InvalidMove);
-            }
+        Self::clear(mv.to, self.get_mut_board(&placed));
+        Self::set(mv.from, self.get_mut_board(&mv.piece));
 
-            self.en_passant = 1u64 << sq;
-        }
-        if BLACK_PMOVES[from as usize] & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidMove);
+        if let MoveType::Castle(castle_type) = mv.move_type {
+            let (rook_from, rook_to) = Self::castle_rook_squares(color, castle_type);
+            let rook = Piece::from_letter('R', color).expect("Invalid piece");
+            Self::clear(rook_to, self.get_mut_board(&rook));
+            Self::set(rook_from, self.get_mut_board(&rook));
         }
-        self.move_piece(from, to, piece)
-    }
 
-    /// Moves a knight
-    pub fn knight_moves(&mut self, from: Square, to: Square, piece: &Piece) -> Result<()> {
-        if KNIGHT_MOVES[from as usize] & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidMove);
+        if let Some(captured_piece) = undo.captured_piece {
+            let square = Self::captured_square(mv);
+            Self::set(square, self.get_mut_board(&captured_piece));
         }
-        self.move_piece(from, to, piece)
-    }
 
-    /// Moves a King
-    pub fn king_moves(&mut self, from: Square, to: Square, piece: &Piece) -> Result<()> {
-        let color = piece.color();
-        if KING_MOVES[from as usize] & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidMove);
-        }
-        self.update_castling_rights(color);
-        self.move_piece(from, to, piece)
+        self.castling_rights = undo.castling_rights;
+        self.en_passant = undo.en_passant;
+        Ok(())
     }
 
-    /// Moves a Rook
-    pub fn rook_moves(&mut self, from: Square, to: Square, piece: &Piece) -> Result<()> {
-        let color = piece.color();
-        if rook_attacks_on_the_fly(from, self.all_pieces()) & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidMove);
+    /// Returns the square of the piece a move captures, which differs from the target square
+    /// for en passant
+    pub fn captured_square(mv: &MoveData) -> Square {
+        match (mv.move_type, mv.piece.color()) {
+            (MoveType::EnPassant, Color::White) => Square::usize_to_square(mv.to as usize - 8),
+            (MoveType::EnPassant, Color::Black) => Square::usize_to_square(mv.to as usize + 8),
+            _ => mv.to,
         }
-        self.revoke_castling_rights(color, from);
-        self.move_piece(from, to, piece)
     }
 
-    /// Moves a Bishop
-    pub fn bishop_moves(&mut self, from: Square, to: Square, piece: &Piece) -> Result<()> {
-        if bishop_attacks_on_the_fly(from, self.all_pieces()) & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidMove);
+    /// Returns the (from, to) squares of the rook when castling
+    pub fn castle_rook_squares(color: Color, castle_type: CastleType) -> (Square, Square) {
+        match (color, castle_type) {
+            (Color::White, CastleType::KingSide) => (Square::H1, Square::F1),
+            (Color::White, CastleType::QueenSide) => (Square::A1, Square::D1),
+            (Color::Black, CastleType::KingSide) => (Square::H8, Square::F8),
+            (Color::Black, CastleType::QueenSide) => (Square::A8, Square::D8),
         }
-        self.move_piece(from, to, piece)
     }
 
-    pub fn queen_moves(&mut self, from: Square, to: Square, piece: &Piece) -> Result<()> {
-        if queen_attacks_on_the_fly(from, self.all_pieces()) & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidMove);
+    pub fn is_under_attack(&self, sq: Square, color: Color) -> bool {
+        match color {
+            Color::White => {
+                if self.white_attack_mask() & (1u64 << sq as usize) == 0 {
+                    return false;
+                }
+                true
+            }
+            Color::Black => {
+                if self.black_attack_mask() & (1u64 << sq as usize) == 0 {
+                    return false;
+                }
+                true
+            }
         }
-        self.move_piece(from, to, piece)
     }
 
-    /** --------------------------------Piece Capture Logic---------------------------------- */
-
-    /// En passant capture
-    pub fn en_passant_capture(&mut self, from: Square, to: Square, piece: &Piece) -> Result<()> {
-        let color = piece.color();
-        // Ensure en passant is possible and the destination is the en passant square
-        if self.en_passant == 0 || self.en_passant & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidCapture);
-        }
-        // Determine the attack pattern based on the piece color
-        let valid_attack = if color == Color::White {
-            WHITE_PATTACKS[from as usize]
-        } else {
-            BLACK_PATTACKS[from as usize]
-        };
+    /** ----------------------------------------- Legal Move Generation ---------------------------------------------- */
 
-        // Validate that the move is a valid en passant capture
-        if valid_attack & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidCapture);
-        }
+    /// Generates every legal move for `color`, including castling, en passant and all four
+    /// promotion choices. Pinned pieces only move along their pin, and when in check only
+    /// evasions are returned (king moves only under double check).
+    pub fn legal_moves(&self, color: Color) -> MoveList {
+        let mut moves = MoveList::new();
 
-        let en_piece: Piece = if color == Color::Black {
-            Piece::WhitePawn
-        } else {
-            Piece::BlackPawn
+        let (own, enemy) = match color {
+            Color::White => (self.white_pieces(), self.black_pieces()),
+            Color::Black => (self.black_pieces(), self.white_pieces()),
         };
+        let occupied = own | enemy;
 
-        // Calculate the captured pawn's square (it is behind the en passant target square)
-        let captured_square = if color == Color::White {
-            to as usize - 8_usize // White pawns move "up" the board, so capture square is "down"
-        } else {
-            to as usize + 8_usize // Black pawns move "down" the board, so capture square is "up"
+        let king = match color {
+            Color::White => self.wK,
+            Color::Black => self.bK,
         };
 
-        self.capture_piece(Square::usize_to_square(captured_square), &en_piece)
-            .and_then(|_| {
-                self.move_piece(from, to, piece)
-                    .map(|_| self.reset_enpassant())
-            })
-    }
+        // Squares a non-king move must land on, and the pin ray of every pinned piece
+        let mut target_mask = !own;
+        let mut pins: Vec<(Bitboard, Bitboard)> = Vec::new();
 
-    /// White pawn captures
-    pub fn wP_captures(
-        &mut self,
-        from: Square,
-        to: Square,
-        piece: &Piece,
-        c_captured: &Piece,
-    ) -> Result<()> {
-        if WHITE_PATTACKS[from as usize] & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidCapture);
-        }
+        if king != 0 {
+            let king_sq = Square::usize_to_square(king.trailing_zeros() as usize);
+            let checkers = self.attackers_to(king_sq, color.opposite(), occupied);
 
-        self.capture_piece(to, c_captured)
-            .and_then(|_| self.move_piece(from, to, piece))
-    }
+            self.generate_king_moves(king_sq, color, own, occupied, &mut moves);
 
-    /// Black pawn captures
-    pub fn bP_captures(
-        &mut self,
-        from: Square,
-        to: Square,
-        piece: &Piece,
-        c_captured: &Piece,
-    ) -> Result<()> {
-        if BLACK_PATTACKS[from as usize] & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidCapture);
-        }
-        self.capture_piece(to, c_captured)
-            .and_then(|_| self.move_piece(from, to, piece))
-    }
+            // Under double check only the king can move
+            if checkers.count_ones() > 1 {
+                return moves;
+            }
 
-    /// Knight Captures
-    pub fn knight_captures(
-        &mut self,
-        from: Square,
-        to: Square,
-        piece: &Piece,
-        c_captured: &Piece,
-    ) -> Result<()> {
-        if KNIGHT_MOVES[from as usize] & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidCapture);
-        }
-        self.capture_piece(to, c_captured)
-            .and_then(|_| self.move_piece(from, to, piece))
-    }
+            if checkers != 0 {
+                let checker = Square::usize_to_square(checkers.trailing_zeros() as usize);
+                target_mask &= checkers | squares_between(king_sq, checker);
+            } else {
+                self.generate_castling_moves(color, occupied, &mut moves);
+            }
 
-    /// King Captures
-    pub fn king_captures(
-        &mut self,
-        from: Square,
-        to: Square,
-        piece: &Piece,
-        c_captured: &Piece,
-    ) -> Result<()> {
-        let color = piece.color();
-        if KING_MOVES[from as usize] & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidCapture);
+            pins = self.pinned_pieces(king_sq, color, own, occupied);
         }
-        self.capture_piece(to, c_captured).and_then(|_| {
-            self.update_castling_rights(color);
-            self.move_piece(from, to, piece)
-        })
-    }
 
-    /// Rook Captures
-    pub fn rook_captures(
-        &mut self,
-        from: Square,
-        to: Square,
-        piece: &Piece,
-        c_captured: &Piece,
-    ) -> Result<()> {
-        let color = piece.color();
-        if rook_attacks_on_the_fly(from, self.all_pieces()) & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidCapture);
+        let mut pieces = own & !king;
+        while pieces != 0 {
+            let from = Square::usize_to_square(pieces.trailing_zeros() as usize);
+            pieces &= pieces - 1;
+
+            let piece = self.get_piece_at(from).expect("Piece not found");
+            let from_bb = 1u64 << from as usize;
+            let pin_mask = pins
+                .iter()
+                .find(|(pinned, _)| pinned & from_bb != 0)
+                .map_or(!0, |&(_, ray)| ray);
+
+            if piece.is_pawn() {
+                self.generate_pawn_moves(
+                    from,
+                    piece,
+                    enemy,
+                    occupied,
+                    target_mask & pin_mask,
+                    &mut moves,
+                );
+                continue;
+            }
+
+            let attacks = match piece {
+                Piece::WhiteKnight | Piece::BlackKnight => KNIGHT_MOVES[from as usize],
+                Piece::WhiteBishop | Piece::BlackBishop => {
+                    bishop_attacks_on_the_fly(from, occupied)
+                }
+                Piece::WhiteRook | Piece::BlackRook => rook_attacks_on_the_fly(from, occupied),
+                Piece::WhiteQueen | Piece::BlackQueen => queen_attacks_on_the_fly(from, occupied),
+                _ => unreachable!(),
+            };
+
+            let mut targets = attacks & target_mask & pin_mask;
+            while targets != 0 {
+                let to = Square::usize_to_square(targets.trailing_zeros() as usize);
+                targets &= targets - 1;
+                moves.push(MoveData::new(from, to, piece, self));
+            }
         }
-        self.capture_piece(to, c_captured).and_then(|_| {
-            self.update_castling_rights(color);
-            self.move_piece(from, to, piece)
-        })
+
+        moves
     }
 
-    /// Bishop Captures
-    pub fn bishop_captures(
-        &mut self,
-        from: Square,
-        to: Square,
-        piece: &Piece,
-        c_captured: &Piece,
-    ) -> Result<()> {
-        if bishop_attacks_on_the_fly(from, self.all_pieces()) & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidCapture);
-        }
-        self.capture_piece(to, c_captured)
-            .and_then(|_| self.move_piece(from, to, piece))
+    /// Returns the bitboard of the pieces of `color` attacking `sq`, for a given occupancy
+    pub fn attackers_to(&self, sq: Square, color: Color, occupied: Bitboard) -> Bitboard {
+        let (pawns, knights, bishops, rooks, queens, kings, pawn_attacks) = match color {
+            Color::White => (
+                self.wP,
+                self.wN,
+                self.wB,
+                self.wR,
+                self.wQ,
+                self.wK,
+                BLACK_PATTACKS[sq as usize],
+            ),
+            Color::Black => (
+                self.bP,
+                self.bN,
+                self.bB,
+                self.bR,
+                self.bQ,
+                self.bK,
+                WHITE_PATTACKS[sq as usize],
+            ),
+        };
+
+        (pawn_attacks & pawns)
+            | (KNIGHT_MOVES[sq as usize] & knights)
+            | (KING_MOVES[sq as usize] & kings)
+            | (bishop_attacks_on_the_fly(sq, occupied) & (bishops | queens))
+            | (rook_attacks_on_the_fly(sq, occupied) & (rooks | queens))
     }
 
-    pub fn queen_captures(
-        &mut self,
-        from: Square,
-        to: Square,
-        piece: &Piece,
-        c_captured: &Piece,
-    ) -> Result<()> {
-        if queen_attacks_on_the_fly(from, self.all_pieces()) & (1u64 << to as usize) == 0 {
-            return Err(ChessError::InvalidCapture);
+    /// Returns every pinned piece of `color` together with the ray it may still move along
+    fn pinned_pieces(
+        &self,
+        king_sq: Square,
+        color: Color,
+        own: Bitboard,
+        occupied: Bitboard,
+    ) -> Vec<(Bitboard, Bitboard)> {
+        let (bishops, rooks, queens) = match color.opposite() {
+            Color::White => (self.wB, self.wR, self.wQ),
+            Color::Black => (self.bB, self.bR, self.bQ),
+        };
+
+        // Enemy sliders which would see the king on an empty board
+        let mut snipers = (bishop_attacks_on_the_fly(king_sq, 0) & (bishops | queens))
+            | (rook_attacks_on_the_fly(king_sq, 0) & (rooks | queens));
+
+        let mut pins = Vec::new();
+        while snipers != 0 {
+            let sniper = Square::usize_to_square(snipers.trailing_zeros() as usize);
+            snipers &= snipers - 1;
+
+            let between = squares_between(king_sq, sniper);
+            let blockers = between & occupied;
+            if blockers.count_ones() == 1 && blockers & own != 0 {
+                pins.push((blockers, between | (1u64 << sniper as usize)));
+            }
         }
-        self.capture_piece(to, c_captured)
-            .and_then(|_| self.move_piece(from, to, piece))
+        pins
     }
 
-    pub fn is_under_attack(&self, sq: Square, color: Color) -> bool {
-        match color {
-            Color::White => {
-                if self.white_attack_mask() & (1u64 << sq as usize) == 0 {
-                    return false;
-                }
-                true
-            }
-            Color::Black => {
-                if self.black_attack_mask() & (1u64 << sq as usize) == 0 {
-                    return false;
-                }
-                true
+    /// Generates the king steps of `color` which do not walk into an attack
+    fn generate_king_moves(
+        &self,
+        king_sq: Square,
+        color: Color,
+        own: Bitboard,
+        occupied: Bitboard,
+        moves: &mut MoveList,
+    ) {
+        let piece = match color {
+            Color::White => Piece::WhiteKing,
+            Color::Black => Piece::BlackKing,
+        };
+        // The king must not shield the squares behind it from a slider
+        let occupied = occupied & !(1u64 << king_sq as usize);
+
+        let mut targets = KING_MOVES[king_sq as usize] & !own;
+        while targets != 0 {
+            let to = Square::usize_to_square(targets.trailing_zeros() as usize);
+            targets &= targets - 1;
+
+            if self.attackers_to(to, color.opposite(), occupied) == 0 {
+                moves.push(MoveData::new(king_sq, to, piece, self));
             }
         }
     }
 
-    /** ----------------------------------------- Castling Logic ---------------------------------------------- */
+    /// Generates castling moves, the king must not be in check nor pass through or land on an
+    /// attacked square
+    fn generate_castling_moves(&self, color: Color, occupied: Bitboard, moves: &mut MoveList) {
+        #[rustfmt::skip]
+        let (king_sq, piece, rook, rights, sides) = match color {
+            Color::White => (Square::E1, Piece::WhiteKing, self.wR, [self.castling_rights[0], self.castling_rights[1]],
+                [(Square::G1, Square::H1, [Square::F1, Square::G1].as_slice(), [Square::F1, Square::G1].as_slice()),
+                 (Square::C1, Square::A1, [Square::B1, Square::C1, Square::D1].as_slice(), [Square::D1, Square::C1].as_slice())]),
+            Color::Black => (Square::E8, Piece::BlackKing, self.bR, [self.castling_rights[2], self.castling_rights[3]],
+                [(Square::G8, Square::H8, [Square::F8, Square::G8].as_slice(), [Square::F8, Square::G8].as_slice()),
+                 (Square::C8, Square::A8, [Square::B8, Square::C8, Square::D8].as_slice(), [Square::D8, Square::C8].as_slice())]),
+        };
 
-    /// White King castling king side
-    pub fn wK_castle_king_side(&mut self) -> Result<()> {
-        let all_pieces = self.all_pieces();
-        let mask_under_attack = [Square::E1, Square::F1, Square::G1];
-        let mask_clear = (1 << Square::F1 as usize) | (1 << Square::G1 as usize);
-        if all_pieces & mask_clear == 0
-            && self.wR & (1 << Square::H1 as usize) != 0
-            && mask_under_attack
-                .iter()
-                .all(|&x| self.is_under_attack(x, Color::White))
-        {
-            self.move_piece(Square::E1, Square::G1, &Piece::WhiteKing)
-                .and_then(|_| {
-                    self.update_castling_rights(Color::White);
-                    self.move_piece(Square::H1, Square::F1, &Piece::WhiteRook)
-                })
-        } else {
-            Err(ChessError::InvalidCastle)
+        if self.get_piece_at(king_sq) != Some(piece) {
+            return;
         }
-    }
 
-    /// White King castling queen side
-    pub fn wK_castle_queen_side(&mut self) -> Result<()> {
-        let all_pieces = self.all_pieces();
-        let mask_under_attack = [Square::E1, Square::D1, Square::C1];
-        let mask_clear =
-            (1 << Square::C1 as usize) | (1 << Square::D1 as usize) | (1 << Square::B1 as usize);
+        for (i, (to, rook_sq, empty, safe)) in sides.into_iter().enumerate() {
+            let castle_type = if i == 0 {
+                CastleType::KingSide
+            } else {
+                CastleType::QueenSide
+            };
 
-        if all_pieces & mask_clear == 0
-            && self.wR & (1 << Square::A1 as usize) != 0
-            && mask_under_attack
-                .iter()
-                .all(|&x| self.is_under_attack(x, Color::White))
-        {
-            self.move_piece(Square::E1, Square::C1, &Piece::WhiteKing)
-                .and_then(|_| {
-                    self.update_castling_rights(Color::White);
-                    self.move_piece(Square::A1, Square::D1, &Piece::WhiteRook)
-                })
-        } else {
-            Err(ChessError::InvalidCastle)
+            if rights[i]
+                && rook & (1u64 << rook_sq as usize) != 0
+                && empty
+                    .iter()
+                    .all(|&sq| occupied & (1u64 << sq as usize) == 0)
+                && safe
+                    .iter()
+                    .all(|&sq| self.attackers_to(sq, color.opposite(), occupied) == 0)
+            {
+                moves.push(MoveData {
+                    from: king_sq,
+                    to,
+                    piece,
+                    move_type: MoveType::Castle(castle_type),
+                });
+            }
         }
     }
 
-    /// Black King castling king side
-    pub fn bK_castle_king_side(&mut self) -> Result<()> {
-        let all_pieces = self.all_pieces();
-        let mask_under_attack = [Square::E8, Square::F8];
-        let mask_clear = (1 << Square::F8 as usize) | (1 << Square::G8 as usize);
-        if all_pieces & mask_clear == 0
-            && self.bR & (1 << Square::H8 as usize) != 0
-            && mask_under_attack
-                .iter()
-                .all(|&x| self.is_under_attack(x, Color::Black))
-        {
-            self.move_piece(Square::E8, Square::G8, &Piece::BlackKing)
-                .and_then(|_| {
-                    self.update_castling_rights(Color::Black);
-                    self.move_piece(Square::H8, Square::F8, &Piece::BlackRook)
-                })
-        } else {
-            Err(ChessError::InvalidCastle)
+    /// Generates pushes, captures, en passant and promotions of a single pawn
+    fn generate_pawn_moves(
+        &self,
+        from: Square,
+        piece: Piece,
+        enemy: Bitboard,
+        occupied: Bitboard,
+        mask: Bitboard,
+        moves: &mut MoveList,
+    ) {
+        let color = piece.color();
+        let (attacks, single, double, promotion_rank) = match color {
+            Color::White => {
+                let single = (1u64 << from as usize) << 8 & !occupied;
+                let double = if from.rank() == 2 {
+                    single << 8 & !occupied
+                } else {
+                    0
+                };
+                (WHITE_PATTACKS[from as usize], single, double, 8)
+            }
+            Color::Black => {
+                let single = (1u64 << from as usize) >> 8 & !occupied;
+                let double = if from.rank() == 7 {
+                    single >> 8 & !occupied
+                } else {
+                    0
+                };
+                (BLACK_PATTACKS[from as usize], single, double, 1)
+            }
+        };
+
+        let mut targets = ((single | double) | (attacks & enemy)) & mask;
+        while targets != 0 {
+            let to = Square::usize_to_square(targets.trailing_zeros() as usize);
+            targets &= targets - 1;
+
+            if to.rank() == promotion_rank {
+                for letter in ['Q', 'R', 'B', 'N'] {
+                    let promoted = Piece::from_letter(letter, color).expect("Invalid piece");
+                    moves.push(MoveData {
+                        from,
+                        to,
+                        piece,
+                        move_type: MoveType::Promotion(promoted),
+                    });
+                }
+            } else {
+                moves.push(MoveData::new(from, to, piece, self));
+            }
         }
-    }
 
-    /// Black King castling queen side
-    pub fn bK_castle_queen_side(&mut self) -> Result<()> {
-        let all_pieces = self.all_pieces();
-        let mask_under_attack = [Square::E8, Square::D8, Square::C8];
-        let mask_clear =
-            (1 << Square::C8 as usize) | (1 << Square::D8 as usize) | (1 << Square::B8 as usize);
+        // En passant can uncover a check along the rank of both pawns, so it is verified on
+        // a copy of the board
+        if attacks & self.en_passant != 0 {
+            let to = Square::usize_to_square(self.en_passant.trailing_zeros() as usize);
+            let captured = match color {
+                Color::White => Square::usize_to_square(to as usize - 8),
+                Color::Black => Square::usize_to_square(to as usize + 8),
+            };
+
+            let mut board = *self;
+            Self::clear(from, board.get_mut_board(&piece));
+            Self::clear(captured, board.get_mut_board(&piece.opp_piece()));
+            Self::set(to, board.get_mut_board(&piece));
 
-        if all_pieces & mask_clear == 0
-            && self.bR & (1 << Square::A8 as usize) != 0
-            && mask_under_attack
-                .iter()
-                .all(|&x| self.is_under_attack(x, Color::Black))
-        {
-            self.move_piece(Square::E8, Square::C8, &Piece::BlackKing)
-                .and_then(|_| {
-                    self.update_castling_rights(Color::Black);
-                    self.move_piece(Square::A8, Square::D8, &Piece::BlackRook)
-                })
-        } else {
-            Err(ChessError::InvalidCastle)
+            let king = match color {
+                Color::White => board.wK,
+                Color::Black => board.bK,
+            };
+            let safe = king == 0
+                || board.attackers_to(
+                    Square::usize_to_square(king.trailing_zeros() as usize),
+                    color.opposite(),
+                    board.all_pieces(),
+                ) == 0;
+
+            if safe {
+                moves.push(MoveData {
+                    from,
+                    to,
+                    piece,
+                    move_type: MoveType::EnPassant,
+                });
+            }
         }
     }
 
@@ -1190,3 +1155,152 @@ impl ChessBoard {
         attacks
     }
 }
+
+/// Returns the squares strictly between two squares on a shared rank, file or diagonal, or an
+/// empty bitboard if they are not aligned
+pub fn squares_between(a: Square, b: Square) -> Bitboard {
+    let a_bb = 1u64 << a as usize;
+    let b_bb = 1u64 << b as usize;
+
+    let diagonal = bishop_attacks_on_the_fly(a, b_bb);
+    if diagonal & b_bb != 0 {
+        return diagonal & bishop_attacks_on_the_fly(b, a_bb);
+    }
+
+    let line = rook_attacks_on_the_fly(a, b_bb);
+    if line & b_bb != 0 {
+        return line & rook_attacks_on_the_fly(b, a_bb);
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(moves: &MoveList, predicate: impl Fn(&MoveData) -> bool) -> usize {
+        moves.iter().filter(|mv| predicate(mv)).count()
+    }
+
+    #[test]
+    fn start_position_has_twenty_moves() {
+        let board = ChessBoard::new();
+        assert_eq!(board.legal_moves(Color::White).len(), 20);
+        assert_eq!(board.legal_moves(Color::Black).len(), 20);
+    }
+
+    #[test]
+    fn pinned_piece_cannot_leave_the_pin() {
+        // The bishop on e2 is pinned by the rook on e8
+        let board = ChessBoard::with_fen("4r1k1/8/8/8/8/8/4B3/4K3 w - - 0 1");
+        let moves = board.legal_moves(Color::White);
+        assert_eq!(count(&moves, |mv| mv.piece == Piece::WhiteBishop), 0);
+        assert_eq!(moves.len(), 4);
+
+        // A pinned rook may still slide along the pin and capture the pinner
+        let board = ChessBoard::with_fen("4r1k1/8/8/8/8/8/4R3/4K3 w - - 0 1");
+        let moves = board.legal_moves(Color::White);
+        assert_eq!(count(&moves, |mv| mv.piece == Piece::WhiteRook), 6);
+    }
+
+    #[test]
+    fn check_evasions_and_double_check() {
+        // Single check from the rook on e8: block, capture or step aside
+        let board = ChessBoard::with_fen("4r1k1/8/8/8/8/8/1B6/R3K3 w - - 0 1");
+        let moves = board.legal_moves(Color::White);
+        assert!(moves.iter().all(|mv| mv.piece == Piece::WhiteKing
+            || mv.to == Square::E5
+            || mv.to == Square::E1
+            || mv.to == Square::E8));
+        assert_eq!(count(&moves, |mv| mv.piece == Piece::WhiteBishop), 1);
+
+        // Double check from the knight on d3 and the rook on e8: only the king moves
+        let board = ChessBoard::with_fen("4r1k1/8/8/8/8/3n4/8/R3K2B w - - 0 1");
+        let moves = board.legal_moves(Color::White);
+        assert!(!moves.is_empty());
+        assert!(moves.iter().all(|mv| mv.piece == Piece::WhiteKing));
+    }
+
+    #[test]
+    fn en_passant_discovering_check_is_illegal() {
+        let board = ChessBoard::with_fen("8/8/8/KPp4r/8/8/8/4k3 w - c6 0 1");
+        let moves = board.legal_moves(Color::White);
+        assert_eq!(count(&moves, |mv| mv.move_type == MoveType::EnPassant), 0);
+
+        let board = ChessBoard::with_fen("8/8/8/1Pp4r/K7/8/8/4k3 w - c6 0 1");
+        let moves = board.legal_moves(Color::White);
+        assert_eq!(count(&moves, |mv| mv.move_type == MoveType::EnPassant), 1);
+    }
+
+    #[test]
+    fn castling_through_attacked_square_is_illegal() {
+        // The rook on f8 covers f1, so only queen side castling is available
+        let board = ChessBoard::with_fen("4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+        let moves = board.legal_moves(Color::White);
+        assert_eq!(
+            count(&moves, |mv| mv.move_type
+                == MoveType::Castle(CastleType::KingSide)),
+            0
+        );
+        assert_eq!(
+            count(&moves, |mv| mv.move_type
+                == MoveType::Castle(CastleType::QueenSide)),
+            1
+        );
+
+        // No castling at all while in check
+        let board = ChessBoard::with_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+        let in_check = ChessBoard::with_fen("4k3/8/8/8/8/8/8/R3K1rR w KQ - 0 1");
+        assert_eq!(
+            count(&board.legal_moves(Color::White), |mv| matches!(
+                mv.move_type,
+                MoveType::Castle(_)
+            )),
+            2
+        );
+        assert_eq!(
+            count(&in_check.legal_moves(Color::White), |mv| matches!(
+                mv.move_type,
+                MoveType::Castle(_)
+            )),
+            0
+        );
+    }
+
+    #[test]
+    fn promotions_offer_all_four_pieces() {
+        let board = ChessBoard::with_fen("1n6/P7/8/8/8/8/8/k6K w - - 0 1");
+        let moves = board.legal_moves(Color::White);
+        let promotions = count(&moves, |mv| matches!(mv.move_type, MoveType::Promotion(_)));
+        // a8 and the capture on b8
+        assert_eq!(promotions, 8);
+    }
+
+    #[test]
+    fn mating_material() {
+        let board = ChessBoard::with_fen("4k3/8/8/8/8/8/8/2N1KB2 w - - 0 1");
+        assert!(board.has_mating_material(Color::White));
+        assert!(!board.has_mating_material(Color::Black));
+
+        let board = ChessBoard::with_fen("4k3/8/8/8/8/8/8/4KB2 w - - 0 1");
+        assert!(!board.has_mating_material(Color::White));
+
+        let board = ChessBoard::with_fen("4k3/7p/8/8/8/8/8/4K3 w - - 0 1");
+        assert!(board.has_mating_material(Color::Black));
+
+        // a knight mates a king blocked by its own pawn
+        let board = ChessBoard::with_fen("4k3/4p3/8/8/8/8/8/1N2K3 w - - 0 1");
+        assert!(board.has_mating_material(Color::White));
+        let board = ChessBoard::with_fen("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1");
+        assert!(!board.has_mating_material(Color::White));
+
+        // bishops on dark squares never cover the light ones
+        let board = ChessBoard::with_fen("4k3/8/8/8/8/8/3B4/2B1K3 w - - 0 1");
+        assert!(!board.has_mating_material(Color::White));
+        let board = ChessBoard::with_fen("4k3/4b3/8/8/8/8/3B4/2B1K3 w - - 0 1");
+        assert!(!board.has_mating_material(Color::White));
+        let board = ChessBoard::with_fen("4k3/3b4/8/8/8/8/3B4/2B1K3 w - - 0 1");
+        assert!(board.has_mating_material(Color::White));
+    }
+}
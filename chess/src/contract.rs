@@ -8,11 +8,13 @@ use std::str::FromStr;
 use self::state::Chess;
 use chess::{
     chessboard::ChessBoard,
+    pgn,
     piece::{Color, Piece},
     square::Square,
     zobrist::PIECE_KEYS,
-    CastleType, ChessError, ChessResponse, Clock, Game, GameChain, GameState,
-    InstantiationArgument, Message, MoveType, Operation, PlayerStats,
+    CastleType, Challenge, ChessError, ChessResponse, Clock, ColorPreference, Game, GameChain,
+    GameResult, GameState, InstantiationArgument, Message, MoveType, Operation, Outcome,
+    PlayerStats, Seek, TakebackRequest, Termination, TimeControl,
 };
 use linera_sdk::{
     base::{
@@ -24,6 +26,13 @@ use linera_sdk::{
     Contract, ContractRuntime,
 };
 
+/// The stake each player of a lobby game or challenge pays, the game chain is funded with both
+/// stakes
+const PLAYER_STAKE: Amount = Amount::ONE;
+
+/// The number of challenges a player can have waiting for an answer
+const MAX_OPEN_CHALLENGES: usize = 5;
+
 #[allow(dead_code)]
 pub struct ChessContract {
     state: Chess,
@@ -80,6 +89,11 @@ impl Contract for ChessContract {
                     // let game = self.state.board.get().with_fen("8/7P/7P/8/8/8/8/7r w - - 0 1");
                     self.state.add_player(player);
                     self.state.board.set(game);
+                    self.state.started_at.set(self.runtime.system_time());
+                    self.state
+                        .clock
+                        .get_mut()
+                        .restart(self.runtime.system_time());
                     ChessResponse::Ok
                 } else {
                     self.state.add_player(player);
@@ -87,17 +101,13 @@ impl Contract for ChessContract {
                 }
             }
 
-            Operation::CapturePiece {
-                from,
-                to,
-                piece,
-                captured_piece,
-            } => {
+            Operation::PlayMove { chess_move } => {
                 // check if the game is still ongoing
-                self.is_game_over();
+                if let ChessResponse::Err(e) = self.is_game_over() {
+                    return ChessResponse::Err(e);
+                }
 
                 let block_time = self.runtime.system_time();
-                let clock = self.state.clock.get_mut();
                 let owner = self.runtime.authenticated_signer().unwrap();
                 let active_player = self.state.board.get().active;
                 let active = self
@@ -115,284 +125,623 @@ impl Contract for ChessContract {
                     );
                 }
 
-                if piece.starts_with("w")
-                    && active_player != Color::White
-                    && captured_piece.starts_with("w")
-                {
-                    return ChessResponse::Err(ChessError::InvalidCapture);
-                }
-                if piece.starts_with("b")
-                    && active_player != Color::Black
-                    && captured_piece.starts_with("b")
-                {
-                    return ChessResponse::Err(ChessError::InvalidCapture);
-                }
-
-                let piece = ChessBoard::get_piece(&piece).expect("Invalid piece");
-                let captured_piece = ChessBoard::get_piece(&captured_piece).expect("Invalid piece");
-                let from_sq = Square::from_str(&from).expect("Invalid square");
-                let to_sq = Square::from_str(&to).expect("Invalid square");
-                let m: MoveType = MoveType::Capture(captured_piece);
+                // the contract derives the piece and the move type from the board
+                let move_data = match self.state.board.get().parse_move(&chess_move) {
+                    Ok(move_data) => move_data,
+                    Err(e) => return ChessResponse::Err(e),
+                };
+                // SAN depends on the position before the move
+                let san = self.state.board.get().to_san(&move_data);
 
-                let success = self
-                    .state
-                    .board
-                    .get_mut()
-                    .make_move(from_sq, to_sq, piece, m);
+                let success = self.state.board.get_mut().make_move(
+                    move_data.from,
+                    move_data.to,
+                    move_data.piece,
+                    move_data.move_type,
+                );
 
                 match success {
                     Ok(_) => {
-                        self.state.board.get_mut().switch_player_turn();
-                        let moves = ChessBoard::create_capture_string(&from, &to);
-                        self.state.board.get_mut().create_move_string(active, moves);
+                        self.state.board.get_mut().create_move_string(active, san);
 
+                        // moving instead of answering declines the opponent's draw offer
+                        if *self.state.draw_offer.get() == Some(active_player.opposite()) {
+                            self.state.draw_offer.set(None);
+                        }
+                        // a takeback request only applies to the position it was made in
+                        self.state.takeback_request.set(None);
+
+                        let clock = self.state.clock.get_mut();
+                        clock.make_move(block_time, active_player);
                         self.runtime
                             .assert_before(block_time.saturating_add(clock.block_delay));
-                        clock.make_move(block_time, active_player);
-
-                        // check for threefold repetition and 50 Move rule, update the game state
-                        if self.state.board.get_mut().check_threefold_repetition()
-                            || self.state.board.get().check_50_move_rule()
-                        {
-                            self.state.board.get_mut().state = GameState::Draw;
-                        }
 
                         // check if the current player is checkmate, i.e if white makes a move after switch turn black is active player and we check if active player is in checkmate
-                        if self.state.board.get_mut().is_checkmate() {
-                            // returns false, if not checkmate
-                            self.state.board.get_mut().state = GameState::Checkmate;
+                        let game = self.state.board.get();
+                        let result = if game.is_checkmate() {
+                            Some(GameResult::win(active_player, Termination::Checkmate))
+                        } else if game.is_stalemate() {
+                            Some(GameResult::draw(Termination::Stalemate))
+                        } else if game.is_insufficient_material() {
+                            Some(GameResult::draw(Termination::InsufficientMaterial))
+                        } else if game.check_fivefold_repetition() {
+                            // fivefold repetition and the 75-move rule end the game, threefold
+                            // repetition and the 50-move rule have to be claimed
+                            Some(GameResult::draw(Termination::Repetition))
+                        } else if game.check_75_move_rule() {
+                            Some(GameResult::draw(Termination::FiftyMove))
+                        } else {
+                            None
                         };
+                        if let Some(result) = result {
+                            return self.end_game(result).await;
+                        }
 
                         ChessResponse::Ok
                     }
-                    Err(_) => panic!("Operation Failed"),
+                    Err(e) => ChessResponse::Err(e),
                 }
             }
+            Operation::Resign => {
+                if let ChessResponse::Err(e) = self.is_game_over() {
+                    return ChessResponse::Err(e);
+                }
 
-            Operation::MakeMove { from, to, piece } => {
-                // check if the game is still ongoing
-                self.is_game_over();
+                // either player can resign, on any turn
+                let resigner = self.signer_color().await;
+                let result = GameResult::win(resigner.opposite(), Termination::Resignation);
+                self.end_game(result).await
+            }
+            Operation::ClaimVictoryOnTime => {
+                if let ChessResponse::Err(e) = self.is_game_over() {
+                    return ChessResponse::Err(e);
+                }
 
-                let owner = self.runtime.authenticated_signer().unwrap();
-                let active_player = self.state.board.get().active;
-                let active = self
-                    .state
-                    .owners
-                    .get(&owner)
-                    .await
-                    .expect("Failed to get active player")
-                    .expect("Active player not found");
+                let block_time = self.runtime.system_time();
+                let claimant = self.signer_color().await;
 
-                if !cfg!(test) {
-                    assert_eq!(
-                        active_player, active,
-                        "Only the active player can make a move."
-                    );
+                // only the waiting player can claim, against the player to move
+                let game = self.state.board.get();
+                if claimant == game.active {
+                    return ChessResponse::Err(ChessError::InvalidRequest);
                 }
-
-                // Early return if the piece is not owned by the active player
-                if piece.starts_with("w") && active_player != Color::White {
-                    return ChessResponse::Err(ChessError::InvalidMove);
+                if !self.state.clock.get().timed_out(block_time, game.active) {
+                    return ChessResponse::Err(ChessError::NotTimedOut);
                 }
 
-                // Early return if the piece is not owned by the active player
-                if piece.starts_with("b") && active_player != Color::Black {
-                    return ChessResponse::Err(ChessError::InvalidMove);
+                let result = if game.board.has_mating_material(claimant) {
+                    GameResult::win(claimant, Termination::Timeout)
+                } else {
+                    GameResult::draw(Termination::Timeout)
+                };
+                self.end_game(result).await
+            }
+            Operation::Abort => {
+                if let ChessResponse::Err(e) = self.is_game_over() {
+                    return ChessResponse::Err(e);
                 }
-
-                let p = ChessBoard::get_piece(&piece).expect("Invalid piece");
-                let from_sq = Square::from_str(&from).expect("Invalid square");
-                let to_sq = Square::from_str(&to).expect("Invalid square");
-                let mut m: MoveType = MoveType::Move;
-
-                if self.state.board.get().board.en_passant & (1u64 << to_sq as usize) != 0
-                    && piece.ends_with("P")
-                {
-                    m = MoveType::EnPassant;
+                if self.state.board.get().history.len() >= 2 {
+                    return ChessResponse::Err(ChessError::AbortNotAllowed);
                 }
 
-                match p {
-                    Piece::WhiteKing => {
-                        // if the player is in check, return
-                        if self.state.board.get().board.in_check(active_player) {
-                            return ChessResponse::Err(ChessError::InvalidMove);
-                        }
-
-                        if from_sq == Square::E1 && to_sq == Square::G1 {
-                            m = MoveType::Castle(CastleType::KingSide);
-                        } else if from_sq == Square::E1 && to_sq == Square::C1 {
-                            m = MoveType::Castle(CastleType::QueenSide);
-                        }
-                    }
-                    Piece::BlackKing => {
-                        // if the player is in check, return
-                        if self.state.board.get().board.in_check(active_player) {
-                            return ChessResponse::Err(ChessError::InvalidMove);
-                        }
-
-                        if from_sq == Square::E8 && to_sq == Square::G8 {
-                            m = MoveType::Castle(CastleType::KingSide);
-                        } else if from_sq == Square::E8 && to_sq == Square::C8 {
-                            m = MoveType::Castle(CastleType::QueenSide);
-                        }
-                    }
-                    _ => {}
+                // only the players can abort, the result leaves the stats untouched
+                self.signer_color().await;
+                self.end_game(GameResult::no_result(Termination::Abandoned))
+                    .await
+            }
+            Operation::OfferDraw => {
+                if let ChessResponse::Err(e) = self.is_game_over() {
+                    return ChessResponse::Err(e);
+                }
+                if self.state.draw_offer.get().is_some() {
+                    return ChessResponse::Err(ChessError::InvalidRequest);
                 }
 
-                let clock = self.state.clock.get_mut();
-                let block_time = self.runtime.system_time();
+                let offerer = self.signer_color().await;
+                self.state.draw_offer.set(Some(offerer));
+                ChessResponse::Ok
+            }
+            Operation::ClaimDraw => {
+                if let ChessResponse::Err(e) = self.is_game_over() {
+                    return ChessResponse::Err(e);
+                }
 
-                let success = self.state.board.get_mut().make_move(from_sq, to_sq, p, m);
+                // only the players can claim, like offering a draw
+                self.signer_color().await;
+                let game = self.state.board.get();
+                let reason = if game.check_threefold_repetition() {
+                    Termination::Repetition
+                } else if game.check_50_move_rule() {
+                    Termination::FiftyMove
+                } else {
+                    return ChessResponse::Err(ChessError::NoDrawClaim);
+                };
+                self.end_game(GameResult::draw(reason)).await
+            }
+            Operation::AcceptDraw | Operation::DeclineDraw => {
+                if let ChessResponse::Err(e) = self.is_game_over() {
+                    return ChessResponse::Err(e);
+                }
 
-                match success {
-                    Ok(_) => {
-                        self.state.board.get_mut().switch_player_turn();
-                        self.state.board.get_mut().create_move_string(active, to);
+                // only the opponent of the offerer can answer the offer
+                let player = self.signer_color().await;
+                if *self.state.draw_offer.get() != Some(player.opposite()) {
+                    return ChessResponse::Err(ChessError::NoDrawOffer);
+                }
 
-                        clock.make_move(block_time, active_player);
-                        self.runtime
-                            .assert_before(block_time.saturating_add(clock.block_delay));
+                self.state.draw_offer.set(None);
+                if matches!(operation, Operation::AcceptDraw) {
+                    return self
+                        .end_game(GameResult::draw(Termination::Agreement))
+                        .await;
+                }
+                ChessResponse::Ok
+            }
+            Operation::RequestTakeback { plies } => {
+                if let ChessResponse::Err(e) = self.is_game_over() {
+                    return ChessResponse::Err(e);
+                }
+                let history = self.state.board.get().history.len();
+                if self.state.takeback_request.get().is_some()
+                    || plies == 0
+                    || plies as usize > history
+                {
+                    return ChessResponse::Err(ChessError::InvalidRequest);
+                }
 
-                        // check for threefold repetition and 50 Move rule, update the game state
-                        if self.state.board.get_mut().check_threefold_repetition()
-                            || self.state.board.get().check_50_move_rule()
-                        {
-                            self.state.board.get_mut().state = GameState::Draw;
-                        }
+                let requester = self.signer_color().await;
+                self.state
+                    .takeback_request
+                    .set(Some(TakebackRequest { requester, plies }));
+                ChessResponse::Ok
+            }
+            Operation::AcceptTakeback => {
+                if let ChessResponse::Err(e) = self.is_game_over() {
+                    return ChessResponse::Err(e);
+                }
 
-                        if self.state.board.get_mut().is_checkmate() {
-                            // returns false, if not checkmate
-                            self.state.board.get_mut().state = GameState::Checkmate;
-                        };
+                // only the opponent of the requester can accept
+                let player = self.signer_color().await;
+                let request = match *self.state.takeback_request.get() {
+                    Some(request) if request.requester == player.opposite() => request,
+                    _ => return ChessResponse::Err(ChessError::NoTakebackRequest),
+                };
 
-                        ChessResponse::Ok
-                    }
-                    Err(e) => ChessResponse::Err(e),
-                    //Err(_) => panic!("Operation Failed"),
+                if let Err(e) = self.state.board.get_mut().takeback(request.plies) {
+                    return ChessResponse::Err(e);
                 }
+                let block_time = self.runtime.system_time();
+                self.state
+                    .clock
+                    .get_mut()
+                    .takeback(request.plies, block_time);
+                self.state.takeback_request.set(None);
+                self.state.draw_offer.set(None);
+                ChessResponse::Ok
             }
-            Operation::PawnPromotion {
-                from,
-                to,
-                piece,
-                promoted_piece,
+            Operation::StartGame {
+                players,
+                amount,
+                time_control,
+                color,
             } => {
-                // check if the game is still ongoing
-                self.is_game_over();
-
-                let from_sq = Square::from_str(&from).expect("Invalid square");
-                let piece = Piece::from_str(&piece).expect("Invalid piece");
-
-                if piece != Piece::WhitePawn && piece != Piece::BlackPawn {
-                    return ChessResponse::Err(ChessError::InvalidPromotion);
+                self.start_game(players, amount, time_control, color, true)
+                    .await
+            }
+            Operation::PostSeek {
+                player,
+                time_control,
+                rated,
+                color_preference,
+                rating_range,
+            } => {
+                if self.runtime.chain_id() != self.main_chain_id() || !self.is_signer(player) {
+                    return ChessResponse::Err(ChessError::InvalidRequest);
+                }
+                if let Err(e) = self.collect_funds(Owner::from(player), PLAYER_STAKE) {
+                    return ChessResponse::Err(e);
                 }
 
-                if piece == Piece::WhitePawn {
-                    if from_sq.rank() != 7 {
-                        return ChessResponse::Err(ChessError::InvalidPromotion);
+                let seek_id = *self.state.next_seek_id.get();
+                let seek = Seek {
+                    seek_id,
+                    player,
+                    time_control,
+                    rated,
+                    color_preference,
+                    rating_range,
+                };
+                self.state.seeks.insert(&seek_id, seek).unwrap();
+                self.state.next_seek_id.set(seek_id + 1);
+                ChessResponse::Ok
+            }
+            Operation::CancelSeek => {
+                let signer = self.runtime.authenticated_signer();
+                for seek in self.state.open_seeks().await {
+                    if Some(Owner::from(seek.player)) == signer {
+                        self.state.seeks.remove(&seek.seek_id).unwrap();
+                        let main_chain_id = self.main_chain_id();
+                        self.refund_stake(Owner::from(seek.player), main_chain_id);
                     }
-                } else if piece == Piece::BlackPawn && from_sq.rank() != 2 {
-                    return ChessResponse::Err(ChessError::InvalidPromotion);
+                }
+                ChessResponse::Ok
+            }
+            Operation::AcceptSeek { seek_id, player } => {
+                let Some(seek) = self.state.seeks.get(&seek_id).await.unwrap() else {
+                    return ChessResponse::Err(ChessError::SeekNotFound);
+                };
+                // nobody plays against their own seek, whichever key they accept it with
+                if !self.is_signer(player) || self.is_signer(seek.player) {
+                    return ChessResponse::Err(ChessError::InvalidRequest);
+                }
+                if let Some(range) = seek.rating_range {
+                    let stats = self.state.player_stats(&Owner::from(player)).await;
+                    if !range.contains(stats.rating()) {
+                        return ChessResponse::Err(ChessError::RatingOutOfRange);
+                    }
+                }
+                if let Err(e) = self.collect_funds(Owner::from(player), PLAYER_STAKE) {
+                    return ChessResponse::Err(e);
                 }
 
-                let block_time = self.runtime.system_time();
+                self.state.seeks.remove(&seek_id).unwrap();
+                self.start_game(
+                    [seek.player, player],
+                    PLAYER_STAKE.saturating_mul(2),
+                    seek.time_control,
+                    seek.color_preference,
+                    seek.rated,
+                )
+                .await
+            }
+            Operation::Challenge {
+                player,
+                opponent,
+                opponent_chain,
+                time_control,
+                color,
+            } => {
+                if !self.is_signer(player) || player == opponent {
+                    return ChessResponse::Err(ChessError::InvalidRequest);
+                }
+                if let Err(e) = self.collect_funds(Owner::from(player), PLAYER_STAKE) {
+                    return ChessResponse::Err(e);
+                }
 
-                let clock = self.state.clock.get_mut();
-                let owner = self.runtime.authenticated_signer().unwrap();
-                let active_player = self.state.board.get().active;
-                let active = self
-                    .state
-                    .owners
-                    .get(&owner)
-                    .await
-                    .expect("Failed to get active player")
-                    .expect("Active player not found");
-                assert_eq!(
-                    active_player, active,
-                    "Only the active player can make a move."
-                );
+                // the main chain records the challenge and forwards it to both players' chains
+                let main_chain_id = self.main_chain_id();
+                self.runtime
+                    .prepare_message(Message::IssueChallenge {
+                        challenger: player,
+                        opponent,
+                        opponent_chain,
+                        time_control,
+                        color,
+                    })
+                    .with_authentication()
+                    .send_to(main_chain_id);
+                ChessResponse::Ok
+            }
+            Operation::AcceptChallenge { challenge_id } => {
+                let Some(challenge) = self.state.challenges.get(&challenge_id).await.unwrap()
+                else {
+                    return ChessResponse::Err(ChessError::ChallengeNotFound);
+                };
+                if !self.is_signer(challenge.opponent) {
+                    return ChessResponse::Err(ChessError::InvalidRequest);
+                }
+                if let Err(e) = self.collect_funds(Owner::from(challenge.opponent), PLAYER_STAKE) {
+                    return ChessResponse::Err(e);
+                }
 
-                let to_sq = Square::from_str(&to).expect("Invalid square");
-                let promoting_to = Piece::from_str(&promoted_piece).expect("Invalid piece");
+                // games are started by the main chain, which keeps track of the game chains
+                self.state.challenges.remove(&challenge_id).unwrap();
+                let main_chain_id = self.main_chain_id();
+                self.runtime
+                    .prepare_message(Message::ChallengeAccepted { challenge_id })
+                    .with_authentication()
+                    .send_to(main_chain_id);
+                ChessResponse::Ok
+            }
+            Operation::DeclineChallenge { challenge_id } => {
+                let Some(challenge) = self.state.challenges.get(&challenge_id).await.unwrap()
+                else {
+                    return ChessResponse::Err(ChessError::ChallengeNotFound);
+                };
+                if !self.is_signer(challenge.opponent) {
+                    return ChessResponse::Err(ChessError::InvalidRequest);
+                }
 
-                let success = self.state.board.get_mut().make_move(
-                    from_sq,
-                    to_sq,
-                    piece,
-                    MoveType::Promotion(promoting_to),
-                );
+                self.state.challenges.remove(&challenge_id).unwrap();
+                let main_chain_id = self.main_chain_id();
+                self.runtime
+                    .prepare_message(Message::ChallengeDeclined { challenge_id })
+                    .with_authentication()
+                    .send_to(main_chain_id);
+                ChessResponse::Ok
+            }
+            Operation::CancelChallenge { challenge_id } => {
+                let Some(challenge) = self.state.challenges.get(&challenge_id).await.unwrap()
+                else {
+                    return ChessResponse::Err(ChessError::ChallengeNotFound);
+                };
+                if !self.is_signer(challenge.challenger) {
+                    return ChessResponse::Err(ChessError::InvalidRequest);
+                }
 
-                match success {
-                    Ok(_) => {
-                        self.state.board.get_mut().switch_player_turn();
-                        self.state.board.get_mut().create_move_string(active, to);
+                self.state.challenges.remove(&challenge_id).unwrap();
+                let main_chain_id = self.main_chain_id();
+                self.runtime
+                    .prepare_message(Message::ChallengeCancelled { challenge_id })
+                    .with_authentication()
+                    .send_to(main_chain_id);
+                ChessResponse::Ok
+            }
+        }
+    }
 
-                        clock.make_move(block_time, active_player);
-                        self.runtime
-                            .assert_before(block_time.saturating_add(clock.block_delay));
+    async fn execute_message(&mut self, message: Self::Message) {
+        match message {
+            Message::Start {
+                players,
+                time_control,
+                color,
+                block_delay,
+            } => {
+                let origin = self
+                    .runtime
+                    .message_id()
+                    .expect("Start is only received as a message")
+                    .chain_id;
+                if origin != self.main_chain_id() {
+                    log::warn!("Ignoring game start from chain {origin}");
+                    return;
+                }
+                // a game in progress is never set up again
+                if !self.state.get_players().is_empty() {
+                    log::warn!("Ignoring game start on a chain that already has players");
+                    return;
+                }
 
-                        self.state.board.get_mut().is_checkmate();
-                        clock.make_move(block_time, active_player);
-                        self.runtime
-                            .assert_before(block_time.saturating_add(clock.block_delay));
+                let block_time = self.runtime.system_time();
+                let coin = ColorPreference::coin(players, self.runtime.chain_id());
 
-                        // check for threefold repetition and 50 Move rule, update the game state
-                        if self.state.board.get_mut().check_threefold_repetition()
-                            || self.state.board.get().check_50_move_rule()
-                        {
-                            self.state.board.get_mut().state = GameState::Draw;
-                        }
+                for (public_key, color) in players.into_iter().zip(color.assign(coin)) {
+                    let owner = Owner::from(public_key);
+                    self.state.owners.insert(&owner, color).unwrap();
+                    self.state.add_player(owner);
+                }
+                self.state.player_keys.set(players.to_vec());
+                self.state.board.set(Game::default().new());
+                self.state.clock.set(Clock::with_time_control(
+                    block_time,
+                    time_control,
+                    block_delay,
+                ));
+                self.state.started_at.set(block_time);
+            }
+            Message::GameOver {
+                result,
+                players,
+                pgn_hash,
+            } => {
+                let origin = self
+                    .runtime
+                    .message_id()
+                    .expect("GameOver is only received as a message")
+                    .chain_id;
+                assert_eq!(
+                    self.runtime.chain_id(),
+                    self.main_chain_id(),
+                    "Results are only reported to the main chain"
+                );
 
-                        if self.state.board.get_mut().is_checkmate() {
-                            // returns false, if not checkmate
-                            self.state.board.get_mut().state = GameState::Checkmate;
-                        };
+                // only the temporary chain opened for both players can report their game
+                let mut rated = true;
+                for public_key in &players {
+                    let game_chains = self.state.game_chains.get(public_key).await.unwrap();
+                    let game = game_chains
+                        .and_then(|chains| chains.into_iter().find(|game| game.chain_id == origin));
+                    let Some(game) = game else {
+                        log::warn!("Ignoring result from unknown game chain {origin}");
+                        return;
+                    };
+                    rated = game.rated;
+                }
 
-                        ChessResponse::Ok
-                    }
-                    Err(_) => panic!("Operation Failed"),
+                log::info!("Game {pgn_hash} on chain {origin} is over");
+                for public_key in &players {
+                    self.state.remove_game_chain(public_key, origin).await;
+                }
+                if rated {
+                    self.handle_match_over(result, players.map(Owner::from))
+                        .await;
                 }
             }
-            Operation::Resign => {
-                self.is_game_over();
-                let owner = self.runtime.authenticated_signer().unwrap();
-                let active_player = self.state.board.get().active;
-                let active = self
-                    .state
-                    .owners
-                    .get(&owner)
+            Message::IssueChallenge {
+                challenger,
+                opponent,
+                opponent_chain,
+                time_control,
+                color,
+            } => {
+                let origin = self
+                    .runtime
+                    .message_id()
+                    .expect("IssueChallenge is only received as a message")
+                    .chain_id;
+                if self.runtime.chain_id() != self.main_chain_id()
+                    || !self.is_signer(challenger)
+                    || challenger == opponent
+                {
+                    log::warn!("Ignoring invalid challenge from chain {origin}");
+                    return;
+                }
+                let mut open_challenges = 0;
+                self.state
+                    .pending_challenges
+                    .for_each_index_value(|_, challenge| {
+                        if challenge.challenger == challenger {
+                            open_challenges += 1;
+                        }
+                        Ok(())
+                    })
                     .await
-                    .expect("Failed to get active player")
-                    .expect("Active player not found");
+                    .unwrap();
+                if open_challenges >= MAX_OPEN_CHALLENGES {
+                    log::warn!("Too many open challenges from chain {origin}");
+                    self.refund_stake(Owner::from(challenger), origin);
+                    return;
+                }
 
-                if !cfg!(test) {
-                    assert_eq!(
-                        active_player, active,
-                        "Only the active player can make a move."
+                let challenge_id = *self.state.next_challenge_id.get();
+                let challenge = Challenge {
+                    challenge_id,
+                    challenger,
+                    challenger_chain: origin,
+                    opponent,
+                    opponent_chain,
+                    time_control,
+                    color,
+                };
+                self.state
+                    .pending_challenges
+                    .insert(&challenge_id, challenge.clone())
+                    .unwrap();
+                self.state.next_challenge_id.set(challenge_id + 1);
+                if opponent_chain != origin {
+                    self.runtime.send_message(
+                        opponent_chain,
+                        Message::Challenge {
+                            challenge: challenge.clone(),
+                        },
                     );
                 }
+                self.runtime
+                    .send_message(origin, Message::Challenge { challenge });
+            }
+            Message::Challenge { challenge } => {
+                let origin = self
+                    .runtime
+                    .message_id()
+                    .expect("Challenge is only received as a message")
+                    .chain_id;
+                if origin != self.main_chain_id() {
+                    log::warn!("Ignoring challenge from chain {origin}");
+                    return;
+                }
+                let challenge_id = challenge.challenge_id;
+                self.state
+                    .challenges
+                    .insert(&challenge_id, challenge)
+                    .unwrap();
+            }
+            Message::ChallengeAccepted { challenge_id } => {
+                let Some(challenge) = self
+                    .answered_challenge(challenge_id, |challenge| {
+                        (challenge.opponent, challenge.opponent_chain)
+                    })
+                    .await
+                else {
+                    // the challenge was cancelled in the meantime, the opponent gets their stake
+                    // back on the chain they accepted it from
+                    if let Some(owner) = self.runtime.authenticated_signer() {
+                        let origin = self.runtime.message_id().unwrap().chain_id;
+                        self.refund_stake(owner, origin);
+                    }
+                    return;
+                };
 
-                self.handle_winner().await;
-
-                self.state.board.get_mut().state = GameState::Resign;
-                ChessResponse::Ok
+                self.runtime.send_message(
+                    challenge.challenger_chain,
+                    Message::ChallengeClosed { challenge_id },
+                );
+                if let ChessResponse::Err(e) = self
+                    .start_game(
+                        [challenge.challenger, challenge.opponent],
+                        PLAYER_STAKE.saturating_mul(2),
+                        challenge.time_control,
+                        challenge.color,
+                        true,
+                    )
+                    .await
+                {
+                    log::warn!("Failed to start the accepted challenge: {e}");
+                }
+            }
+            Message::ChallengeDeclined { challenge_id } => {
+                let Some(challenge) = self
+                    .answered_challenge(challenge_id, |challenge| {
+                        (challenge.opponent, challenge.opponent_chain)
+                    })
+                    .await
+                else {
+                    return;
+                };
+                self.refund_stake(
+                    Owner::from(challenge.challenger),
+                    challenge.challenger_chain,
+                );
+                self.runtime.send_message(
+                    challenge.challenger_chain,
+                    Message::ChallengeClosed { challenge_id },
+                );
+            }
+            Message::ChallengeCancelled { challenge_id } => {
+                let Some(challenge) = self
+                    .answered_challenge(challenge_id, |challenge| {
+                        (challenge.challenger, challenge.challenger_chain)
+                    })
+                    .await
+                else {
+                    return;
+                };
+                self.refund_stake(
+                    Owner::from(challenge.challenger),
+                    challenge.challenger_chain,
+                );
+                self.runtime.send_message(
+                    challenge.opponent_chain,
+                    Message::ChallengeClosed { challenge_id },
+                );
+            }
+            Message::ChallengeClosed { challenge_id } => {
+                let origin = self
+                    .runtime
+                    .message_id()
+                    .expect("ChallengeClosed is only received as a message")
+                    .chain_id;
+                if origin != self.main_chain_id() {
+                    log::warn!("Ignoring closed challenge from chain {origin}");
+                    return;
+                }
+                self.state.challenges.remove(&challenge_id).unwrap();
             }
-            Operation::StartGame {
-                players,
-                amount,
-                match_time,
-            } => self.start_game(players, amount, match_time).await,
         }
     }
 
-    async fn execute_message(&mut self, _message: Self::Message) {}
-
     async fn store(mut self) {
         self.state.save().await.expect("Failed to save state");
     }
 }
 
 impl ChessContract {
+    /// Returns true if the operation is signed by the owner of the public key
+    pub fn is_signer(&mut self, player: PublicKey) -> bool {
+        self.runtime.authenticated_signer() == Some(Owner::from(player))
+    }
+
+    /// Returns the color played by the signer of the current operation
+    pub async fn signer_color(&mut self) -> Color {
+        let owner = self.runtime.authenticated_signer().unwrap();
+        self.state
+            .owners
+            .get(&owner)
+            .await
+            .expect("Failed to get player")
+            .expect("Player not found")
+    }
+
     pub fn is_game_over(&self) -> ChessResponse {
         match self.state.board.get().state {
             GameState::Checkmate => ChessResponse::Err(ChessError::InvalidRequest),
@@ -400,6 +749,9 @@ impl ChessContract {
             GameState::Draw => ChessResponse::Err(ChessError::InvalidRequest),
             GameState::InPlay => ChessResponse::Ok,
             GameState::Resign => ChessResponse::Err(ChessError::InvalidRequest),
+            GameState::Timeout => ChessResponse::Err(ChessError::InvalidRequest),
+            GameState::InsufficientMaterial => ChessResponse::Err(ChessError::InvalidRequest),
+            GameState::Abandoned => ChessResponse::Err(ChessError::InvalidRequest),
         }
     }
 
@@ -409,7 +761,9 @@ impl ChessContract {
         &mut self,
         players: [PublicKey; 2],
         amount: Amount,
-        match_time: TimeDelta,
+        time_control: TimeControl,
+        color: ColorPreference,
+        rated: bool,
     ) -> ChessResponse {
         assert_eq!(self.runtime.chain_id(), self.main_chain_id());
         let ownership = ChainOwnership::multiple(
@@ -429,13 +783,16 @@ impl ChessContract {
                 .insert(GameChain {
                     message_id,
                     chain_id,
+                    rated,
                 });
         }
         self.runtime.send_message(
             chain_id,
             Message::Start {
                 players,
-                timer: match_time,
+                time_control,
+                color,
+                block_delay: self.state.clock.get().block_delay,
             },
         );
         ChessResponse::Ok
@@ -446,40 +803,174 @@ impl ChessContract {
         self.runtime.application_creator_chain_id()
     }
 
-    /// Handles the winner stats, when a match is over, this function is called to update the
-    /// leaderboard.
-    /// Can only be update by the creation chain(Todo!)
-    pub fn handle_match_over(&mut self, winner: PlayerStats) {
-        let last_player = self.state.bottom_player_stats();
-        if last_player.wins > winner.wins {
-            return;
+    /// Takes an answered challenge out of the main chain's records, if the message was signed by
+    /// the player returned by `sender` on their own chain
+    pub async fn answered_challenge(
+        &mut self,
+        challenge_id: u64,
+        sender: fn(&Challenge) -> (PublicKey, ChainId),
+    ) -> Option<Challenge> {
+        let origin = self
+            .runtime
+            .message_id()
+            .expect("Challenge answers are only received as messages")
+            .chain_id;
+        if self.runtime.chain_id() == self.main_chain_id() {
+            let pending = self.state.pending_challenges.get(&challenge_id).await;
+            if let Some(challenge) = pending.unwrap() {
+                let (player, player_chain) = sender(&challenge);
+                if origin == player_chain && self.is_signer(player) {
+                    self.state.pending_challenges.remove(&challenge_id).unwrap();
+                    return Some(challenge);
+                }
+            }
+        }
+        log::warn!("Ignoring answer to challenge {challenge_id} from chain {origin}");
+        None
+    }
+
+    /// Moves `amount` from the account of `owner` to the balance of the main chain, which funds
+    /// the game chains
+    pub fn collect_funds(&mut self, owner: Owner, amount: Amount) -> Result<(), ChessError> {
+        if self.runtime.owner_balance(owner) < amount {
+            return Err(ChessError::InsufficientBalance);
+        }
+        let main_chain = Account {
+            chain_id: self.main_chain_id(),
+            owner: None,
+        };
+        self.runtime.transfer(Some(owner), main_chain, amount);
+        Ok(())
+    }
+
+    /// Returns a stake that was not played from the main chain to the player's account on
+    /// `chain_id`
+    pub fn refund_stake(&mut self, player: Owner, chain_id: ChainId) {
+        let account = Account {
+            chain_id,
+            owner: Some(player),
+        };
+        self.runtime.transfer(None, account, PLAYER_STAKE);
+    }
+
+    /// Refunds the remaining balance of a temporary game chain in equal parts to the players'
+    /// accounts on the main chain and closes it, the main chain is never closed
+    pub fn close_game_chain(&mut self) {
+        let main_chain_id = self.main_chain_id();
+        if self.runtime.chain_id() == main_chain_id {
+            return;
+        }
+
+        let balance = self.runtime.chain_balance();
+        let share = Amount::from_attos(balance.saturating_div(Amount::from_attos(2)));
+        if share > Amount::ZERO {
+            for public_key in self.state.player_keys.get().clone() {
+                let account = Account {
+                    chain_id: main_chain_id,
+                    owner: Some(Owner::from(public_key)),
+                };
+                self.runtime.transfer(None, account, share);
+            }
+        }
+        self.runtime
+            .close_chain()
+            .expect("The application does not have permission to close the game chain");
+    }
+
+    /// Ends the current game with the given result
+    pub async fn end_game(&mut self, result: GameResult) -> ChessResponse {
+        self.state.board.get_mut().finish(result);
+        match self.handle_winner(result).await {
+            Ok(()) => ChessResponse::Ok,
+            Err(e) => ChessResponse::Err(e),
+        }
+    }
+
+    /// Handles the stats of the White and Black player, when a match is over, this function is
+    /// called to update the leaderboard. Games without a result, like aborts, are not counted.
+    pub async fn handle_match_over(&mut self, result: GameResult, players: [Owner; 2]) {
+        if result.outcome == Outcome::NoResult {
+            return;
+        }
+
+        for (player, color) in players.into_iter().zip([Color::White, Color::Black]) {
+            let mut stats = self.state.player_stats(&player).await;
+            match result.winner() {
+                Some(winner) if winner == color => stats.record_win(),
+                Some(_) => stats.record_loss(),
+                None => stats.record_draw(),
+            }
+            self.state.stats.insert(&player, stats.clone()).unwrap();
+            self.state.add_player_leaderboard(stats);
+        }
+    }
+
+    /// Reports the result of the game on a temporary chain to the main chain
+    pub async fn report_result(&mut self, result: GameResult) -> Result<(), ChessError> {
+        let keys = self.state.player_keys.get();
+        let (Some(&first_key), Some(&second_key)) = (keys.first(), keys.get(1)) else {
+            return Err(ChessError::PlayersNotFound);
+        };
+        let mut players = [first_key, second_key];
+        let first = self
+            .state
+            .owners
+            .get(&Owner::from(players[0]))
+            .await
+            .unwrap();
+        if first == Some(Color::Black) {
+            players.swap(0, 1);
+        }
+
+        let pgn_hash = pgn::hash(&self.state.pgn().await);
+        let main_chain_id = self.main_chain_id();
+        self.runtime.send_message(
+            main_chain_id,
+            Message::GameOver {
+                result,
+                players,
+                pgn_hash,
+            },
+        );
+        Ok(())
+    }
+
+    /// Handles the winner of the game, when a match is over. A temporary game chain reports the
+    /// result to the main chain, which updates the stats of the winner and the loser
+    pub async fn handle_winner(&mut self, result: GameResult) -> Result<(), ChessError> {
+        if self.runtime.chain_id() != self.main_chain_id() {
+            self.report_result(result).await?;
+            self.close_game_chain();
+            return Ok(());
         }
 
-        self.state.add_player_leaderboard(winner);
-    }
-
-    /// Handles the winner of the game, when a match is over
-    pub async fn handle_winner(&mut self) {
-        // self.send_reward_nft().await;
+        let white = self.state.player_with_color(Color::White).await;
+        let black = self.state.player_with_color(Color::Black).await;
+        if let (Some(white), Some(black)) = (white, black) {
+            self.handle_match_over(result, [white, black]).await;
+        }
         // if players were betting on the game. send the amount to the winner(Todo!)
-        // it will require punk records
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     #![allow(unused_imports)]
-    use std::str::FromStr;
-
     use chess::{
+        pgn,
         piece::{Color, Piece},
-        ChessError, ChessResponse, InstantiationArgument, Operation,
+        ChessError, ChessResponse, ColorPreference, Game, GameChain, GameResult, GameState,
+        InstantiationArgument, Message, Operation, Outcome, PlayerStats, RatingRange, Termination,
+        TimeControl, TimeMode,
     };
-    use env_logger;
     use futures::FutureExt as _;
 
     use linera_sdk::{
-        base::Owner,
+        base::{
+            Account, Amount, ApplicationId, ApplicationPermissions, BlockHeight, ChainId,
+            ChainOwnership, Destination, MessageId, Owner, PublicKey, TimeDelta, TimeoutConfig,
+        },
         contract::MockContractRuntime,
         util::BlockingWait,
         views::{View, ViewStorageContext},
@@ -487,29 +978,23 @@ mod tests {
     };
     use log::LevelFilter;
 
-    use super::{Chess, ChessContract};
+    use super::{Chess, ChessContract, MAX_OPEN_CHALLENGES, PLAYER_STAKE};
+    use fixtures::*;
 
     #[test]
     fn new_game() {
         env_logger::builder().filter_level(LevelFilter::Info).init();
 
-        let owner1 =
-            Owner::from_str("df44403a282330a8b086603516277c014c844a4b418835873aced1132a3adcd5")
-                .unwrap();
-        let owner2 =
-            Owner::from_str("43c319a4eab3747afcd608d32b73a2472fcaee390ec6bed3e694b4908f55772d")
-                .unwrap();
-
         // Setting Players through InstantiationArgument
         let initial_value = InstantiationArgument {
-            players: [owner1, owner2],
-            start_time: 600000000.into(),
-            increment: 600000000.into(),
+            players: owners(),
+            time_control: time_control(600, 600),
             block_delay: 100000000.into(),
         };
-        let mut app = create_and_instantiate_app(initial_value.clone(), owner1);
+        let mut app = instantiated_app(initial_value.clone());
 
         let players = initial_value.players;
+        assert_eq!(app.state.clock.get().time_control.to_pgn(), "600+600");
 
         let mut response = ChessResponse::Ok;
 
@@ -533,7 +1018,7 @@ mod tests {
         // Test alternating moves:
 
         // White makes a valid pawn move from a2 to a3
-        response = make_move(&mut app, "a2", "a3", "wP");
+        response = play_move(&mut app, "a2a3");
         assert_eq!(response, ChessResponse::Ok, "Pawn move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -544,7 +1029,7 @@ mod tests {
         log::info!("{:?}", app.state.board.get().active);
 
         // Black makes a valid pawn move from b7 to b6
-        response = make_move(&mut app, "b7", "b6", "bP");
+        response = play_move(&mut app, "b7b6");
         assert_eq!(response, ChessResponse::Ok, "Pawn move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -553,7 +1038,7 @@ mod tests {
         );
 
         // White attempts an illegal knight move from g1 to g5 (invalid)
-        response = make_move(&mut app, "g1", "g5", "wN");
+        response = play_move(&mut app, "g1g5");
         assert_eq!(
             response,
             ChessResponse::Err(ChessError::InvalidMove),
@@ -566,7 +1051,7 @@ mod tests {
         );
 
         // White makes a valid knight move from g1 to f3
-        response = make_move(&mut app, "g1", "f3", "wN");
+        response = play_move(&mut app, "Nf3");
         assert_eq!(response, ChessResponse::Ok, "Knight move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -575,7 +1060,7 @@ mod tests {
         );
 
         // Black makes a invalid bishop move from f8 to c5(pawn on e7 blocks the move)
-        response = make_move(&mut app, "f8", "c5", "bB");
+        response = play_move(&mut app, "f8c5");
         assert_eq!(
             response,
             ChessResponse::Err(ChessError::InvalidMove),
@@ -588,7 +1073,7 @@ mod tests {
         );
 
         // Black makes a valid pawn move from e7 to e6
-        response = make_move(&mut app, "e7", "e6", "bP");
+        response = play_move(&mut app, "e6");
         assert_eq!(response, ChessResponse::Ok, "Pawn move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -597,7 +1082,7 @@ mod tests {
         );
 
         // White attempts an illegal pawn move from a3 to a5 (invalid)
-        response = make_move(&mut app, "a3", "a5", "wP");
+        response = play_move(&mut app, "a3a5");
         assert_eq!(
             response,
             ChessResponse::Err(ChessError::InvalidMove),
@@ -609,8 +1094,16 @@ mod tests {
             "Active player is still White"
         );
 
+        // White sends a move the contract cannot read
+        response = play_move(&mut app, "Zz9");
+        assert_eq!(
+            response,
+            ChessResponse::Err(ChessError::InvalidNotation),
+            "Move notation should be invalid"
+        );
+
         // White makes a Invalid bishop move from c1 to f4(pawn is blocking at d2)
-        response = make_move(&mut app, "c1", "f4", "wB");
+        response = play_move(&mut app, "c1f4");
         assert_eq!(
             response,
             ChessResponse::Err(ChessError::InvalidMove),
@@ -623,7 +1116,7 @@ mod tests {
         );
 
         // White makes a valid pawn move from e2 to e4
-        response = make_move(&mut app, "e2", "e4", "wP");
+        response = play_move(&mut app, "e2e4");
         assert_eq!(response, ChessResponse::Ok, "Pawn move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -632,7 +1125,7 @@ mod tests {
         );
 
         // Black attempts an illegal bishop move from f4 to h4 (invalid)
-        response = make_move(&mut app, "f4", "h4", "bB");
+        response = play_move(&mut app, "f4h4");
         assert_eq!(
             response,
             ChessResponse::Err(ChessError::InvalidMove),
@@ -645,7 +1138,7 @@ mod tests {
         );
 
         // Black makes a valid queen move from d8 to d4
-        response = make_move(&mut app, "d8", "g5", "bQ");
+        response = play_move(&mut app, "d8g5");
         assert_eq!(response, ChessResponse::Ok, "Queen move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -654,7 +1147,7 @@ mod tests {
         );
 
         // White makes a king move from e1 to e2 (no check yet)
-        response = make_move(&mut app, "e1", "e2", "wK");
+        response = play_move(&mut app, "e1e2");
         assert_eq!(response, ChessResponse::Ok, "King move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -663,7 +1156,7 @@ mod tests {
         );
 
         // Black attempts to move the queen but is now in check (this would fail the check)
-        response = make_move(&mut app, "d4", "d5", "bQ");
+        response = play_move(&mut app, "d4d5");
         assert_eq!(
             response,
             ChessResponse::Err(ChessError::InvalidMove),
@@ -671,7 +1164,7 @@ mod tests {
         );
 
         // Black resolves the check and makes a valid queen move from g5 to a5
-        response = make_move(&mut app, "g5", "a5", "bQ");
+        response = play_move(&mut app, "g5a5");
         assert_eq!(response, ChessResponse::Ok, "Queen move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -681,7 +1174,7 @@ mod tests {
 
         // (c3 d5 f6), (f8, a3, bB, wP)
         // White makes a Knight move from b1 to c3
-        response = make_move(&mut app, "b1", "c3", "wN");
+        response = play_move(&mut app, "b1c3");
         assert_eq!(response, ChessResponse::Ok, "knight move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -690,7 +1183,7 @@ mod tests {
         );
 
         // Black captures a piece (from f8 bB captures wP a3)
-        response = capture_piece(&mut app, "f8", "a3", "bB", "wP");
+        response = play_move(&mut app, "Bxa3");
         assert_eq!(response, ChessResponse::Ok, "Knight move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -699,7 +1192,7 @@ mod tests {
         );
 
         // White makes a Knight move from c3 to d5
-        response = make_move(&mut app, "c3", "d5", "wN");
+        response = play_move(&mut app, "c3d5");
         assert_eq!(response, ChessResponse::Ok, "Knight move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -708,7 +1201,7 @@ mod tests {
         );
 
         // Black makes a bishop move
-        response = make_move(&mut app, "c8", "b7", "bB");
+        response = play_move(&mut app, "Bb7");
         assert_eq!(response, ChessResponse::Ok, "Bishop move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -717,7 +1210,7 @@ mod tests {
         );
 
         // White makes a Knight move from d5 to f6 (puts black's king in check)
-        response = make_move(&mut app, "d5", "f6", "wN");
+        response = play_move(&mut app, "Nf6+");
         assert_eq!(response, ChessResponse::Ok, "Knight move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -727,18 +1220,11 @@ mod tests {
 
         let game_data = app.state.board.get();
 
-        log::info!(
-            "black king before check {:?}",
-            app.state.board.get().board.to_fen(
-                &game_data.active_player(),
-                &game_data.halfmove_clock,
-                &game_data.fullmove_count
-            )
-        );
+        log::info!("black king before check {:?}", game_data.to_fen());
 
         // black bishop needs to make a capture to get its king out of check(g8 to f6 wbK)
         // Black captures a piece (from f8 bB captures wP a3)
-        response = capture_piece(&mut app, "g8", "f6", "bN", "wN");
+        response = play_move(&mut app, "g8f6");
         assert_eq!(response, ChessResponse::Ok, "Knight move should be valid");
         assert_eq!(
             app.state.board.get().active,
@@ -750,66 +1236,1014 @@ mod tests {
 
         log::info!(
             "black moved and king is out of check now {:?}",
-            app.state.board.get().board.to_fen(
-                &game_data.active_player(),
-                &game_data.halfmove_clock,
-                &game_data.fullmove_count
-            )
+            game_data.to_fen()
         );
     }
 
-    fn make_move(app: &mut ChessContract, from: &str, to: &str, piece: &str) -> ChessResponse {
-        let response = app
-            .execute_operation(Operation::MakeMove {
-                from: from.to_string(),
-                to: to.to_string(),
-                piece: piece.to_string(),
-            })
-            .now_or_never()
-            .expect("Execution of application operation should not await anything");
+    #[test]
+    fn timeout_claims() {
+        let mut app = joined_app();
+
+        // White cannot claim on its own turn
+        assert_eq!(
+            claim_victory_on_time(&mut app),
+            ChessResponse::Err(ChessError::InvalidRequest)
+        );
+        assert_eq!(play_move(&mut app, "e4"), ChessResponse::Ok);
+        assert_eq!(
+            claim_victory_on_time(&mut app),
+            ChessResponse::Err(ChessError::NotTimedOut)
+        );
+
+        // Black lets its 15 minutes run out
+        app.runtime.set_system_time(1_001_000_000.into());
+        assert_eq!(claim_victory_on_time(&mut app), ChessResponse::Ok);
+        assert_eq!(app.state.board.get().state, GameState::Timeout);
+        assert_eq!(
+            app.state.board.get().result,
+            Some(GameResult::win(Color::White, Termination::Timeout))
+        );
+        assert_eq!(
+            play_move(&mut app, "e5"),
+            ChessResponse::Err(ChessError::InvalidRequest)
+        );
 
-        response
+        // A lone king cannot win on time
+        let mut app = joined_app();
+        app.state
+            .board
+            .set(Game::from_fen("4k3/pppppppp/8/8/8/8/8/4K3 b - - 0 1").unwrap());
+        app.runtime.set_system_time(1_001_000_000.into());
+        assert_eq!(claim_victory_on_time(&mut app), ChessResponse::Ok);
+        assert_eq!(
+            app.state.board.get().result,
+            Some(GameResult::draw(Termination::Timeout))
+        );
     }
 
-    fn capture_piece(
-        app: &mut ChessContract,
-        from: &str,
-        to: &str,
-        piece: &str,
-        captured_piece: &str,
-    ) -> ChessResponse {
-        let response = app
-            .execute_operation(Operation::CapturePiece {
-                from: from.to_string(),
-                to: to.to_string(),
-                piece: piece.to_string(),
-                captured_piece: captured_piece.to_string(),
-            })
-            .now_or_never()
-            .expect("Execution of application operation should not await anything");
+    #[test]
+    fn stalemate_ends_the_game() {
+        let mut app = joined_app();
+        app.state
+            .board
+            .set(Game::from_fen("7k/8/6Q1/8/8/8/8/K7 w - - 0 1").unwrap());
+
+        assert_eq!(play_move(&mut app, "Qf7"), ChessResponse::Ok);
+        assert_eq!(app.state.board.get().state, GameState::Stalemate);
+        assert_eq!(
+            app.state.board.get().result,
+            Some(GameResult::draw(Termination::Stalemate))
+        );
+    }
+
+    #[test]
+    fn insufficient_material_ends_the_game() {
+        let mut app = joined_app();
+        app.state
+            .board
+            .set(Game::from_fen("4k3/8/8/8/8/8/4p3/3BK3 w - - 0 1").unwrap());
+
+        assert_eq!(play_move(&mut app, "Kxe2"), ChessResponse::Ok);
+        assert_eq!(app.state.board.get().state, GameState::InsufficientMaterial);
+        assert_eq!(
+            play_move(&mut app, "Kd7"),
+            ChessResponse::Err(ChessError::InvalidRequest)
+        );
+    }
+
+    #[test]
+    fn resign_on_either_turn() {
+        let mut app = joined_app();
+        let [white, black] = owners();
+
+        // White resigns while Black is to move
+        assert_eq!(play_move(&mut app, "e4"), ChessResponse::Ok);
+        assert_eq!(execute(&mut app, Operation::Resign), ChessResponse::Ok);
+        assert_eq!(
+            app.state.board.get().result,
+            Some(GameResult::win(Color::Black, Termination::Resignation))
+        );
+        assert_eq!(
+            execute(&mut app, Operation::Resign),
+            ChessResponse::Err(ChessError::InvalidRequest)
+        );
+
+        let winner = app.state.player_stats(&black).blocking_wait();
+        assert_eq!((winner.games_played, winner.wins, winner.losses), (1, 1, 0));
+        assert_eq!(winner.win_rate, 1.0);
+        let loser = app.state.player_stats(&white).blocking_wait();
+        assert_eq!((loser.games_played, loser.wins, loser.losses), (1, 0, 1));
+        assert_eq!(loser.win_rate, 0.0);
+    }
+
+    #[test]
+    fn aborts() {
+        let mut app = game_chain_app(ColorPreference::White);
+
+        // White moved, Black can still abort
+        assert_eq!(play_move(&mut app, "e4"), ChessResponse::Ok);
+        assert_eq!(execute(&mut app, Operation::Abort), ChessResponse::Ok);
+        assert_eq!(app.state.board.get().state, GameState::Abandoned);
+        assert_eq!(
+            app.state.board.get().result,
+            Some(GameResult::no_result(Termination::Abandoned))
+        );
+        assert_eq!(pgn::result(app.state.board.get()), "*");
+        assert!(matches!(
+            app.runtime.created_send_message_requests().last().unwrap().message,
+            Message::GameOver { result, .. } if result.outcome == Outcome::NoResult
+        ));
+        // the leftover balance is split between the players' accounts on the main chain
+        for owner in owners() {
+            let account = Account {
+                chain_id: ChainId::root(0),
+                owner: Some(owner),
+            };
+            assert_eq!(
+                app.runtime.outgoing_transfers().get(&account),
+                Some(&Amount::from_millis(500))
+            );
+        }
+        assert_eq!(app.runtime.chain_balance(), Amount::ZERO);
+
+        // Once both players moved, the game has to be resigned
+        let mut app = joined_app();
+        assert_eq!(play_move(&mut app, "e4"), ChessResponse::Ok);
+        assert_eq!(play_move(&mut app, "e5"), ChessResponse::Ok);
+        assert_eq!(
+            execute(&mut app, Operation::Abort),
+            ChessResponse::Err(ChessError::AbortNotAllowed)
+        );
+    }
+
+    #[test]
+    fn results_need_both_players_to_be_reported() {
+        let mut app = game_chain_app(ColorPreference::White);
+        app.state.player_keys.set(Vec::new());
+
+        assert_eq!(
+            execute(&mut app, Operation::Resign),
+            ChessResponse::Err(ChessError::PlayersNotFound)
+        );
+        // the game chain stays open with its balance
+        assert!(app.runtime.outgoing_transfers().is_empty());
+        assert_eq!(app.runtime.chain_balance(), Amount::from_tokens(1));
+    }
+
+    #[test]
+    fn draw_offers() {
+        let mut app = joined_app();
+        let [white, black] = owners();
+
+        // White offers and cannot accept its own offer
+        assert_eq!(execute(&mut app, Operation::OfferDraw), ChessResponse::Ok);
+        assert_eq!(
+            execute(&mut app, Operation::OfferDraw),
+            ChessResponse::Err(ChessError::InvalidRequest)
+        );
+        assert_eq!(
+            execute(&mut app, Operation::AcceptDraw),
+            ChessResponse::Err(ChessError::NoDrawOffer)
+        );
+
+        // The offer survives White's own move and expires when Black moves
+        assert_eq!(play_move(&mut app, "e4"), ChessResponse::Ok);
+        assert_eq!(*app.state.draw_offer.get(), Some(Color::White));
+        app.runtime.set_authenticated_signer(black);
+        assert_eq!(play_move(&mut app, "e5"), ChessResponse::Ok);
+        assert_eq!(*app.state.draw_offer.get(), None);
+
+        // Black declines a second offer, then accepts a third
+        app.runtime.set_authenticated_signer(white);
+        assert_eq!(execute(&mut app, Operation::OfferDraw), ChessResponse::Ok);
+        app.runtime.set_authenticated_signer(black);
+        assert_eq!(execute(&mut app, Operation::DeclineDraw), ChessResponse::Ok);
+        assert_eq!(*app.state.draw_offer.get(), None);
+        assert_eq!(app.state.board.get().state, GameState::InPlay);
+
+        app.runtime.set_authenticated_signer(white);
+        assert_eq!(execute(&mut app, Operation::OfferDraw), ChessResponse::Ok);
+        app.runtime.set_authenticated_signer(black);
+        assert_eq!(execute(&mut app, Operation::AcceptDraw), ChessResponse::Ok);
+        assert_eq!(app.state.board.get().state, GameState::Draw);
+        assert_eq!(
+            execute(&mut app, Operation::OfferDraw),
+            ChessResponse::Err(ChessError::InvalidRequest)
+        );
+    }
+
+    #[test]
+    fn takebacks() {
+        let mut app = joined_app();
+        let [white, black] = owners();
+        let start = app.state.board.get().clone();
+
+        assert_eq!(
+            execute(&mut app, Operation::RequestTakeback { plies: 1 }),
+            ChessResponse::Err(ChessError::InvalidRequest)
+        );
+        app.runtime.set_system_time(110000000.into());
+        assert_eq!(play_move(&mut app, "e4"), ChessResponse::Ok);
+        app.runtime.set_authenticated_signer(black);
+        app.runtime.set_system_time(130000000.into());
+        assert_eq!(play_move(&mut app, "d5"), ChessResponse::Ok);
+        let before_capture = app.state.board.get().clone();
+        let clock_before_capture = app.state.clock.get().time_left;
+        app.runtime.set_authenticated_signer(white);
+        app.runtime.set_system_time(160000000.into());
+        assert_eq!(play_move(&mut app, "exd5"), ChessResponse::Ok);
+
+        // White asks to take back the capture, a move by Black would cancel the request
+        assert_eq!(
+            execute(&mut app, Operation::RequestTakeback { plies: 1 }),
+            ChessResponse::Ok
+        );
+        assert_eq!(
+            execute(&mut app, Operation::AcceptTakeback),
+            ChessResponse::Err(ChessError::NoTakebackRequest)
+        );
+        app.runtime.set_authenticated_signer(black);
+        assert_eq!(
+            execute(&mut app, Operation::AcceptTakeback),
+            ChessResponse::Ok
+        );
+        assert_eq!(*app.state.board.get(), before_capture);
+        assert_eq!(app.state.clock.get().time_left, clock_before_capture);
+        assert_eq!(*app.state.takeback_request.get(), None);
+
+        // Black asks to take back both remaining moves
+        assert_eq!(
+            execute(&mut app, Operation::RequestTakeback { plies: 2 }),
+            ChessResponse::Ok
+        );
+        app.runtime.set_authenticated_signer(white);
+        assert_eq!(
+            execute(&mut app, Operation::AcceptTakeback),
+            ChessResponse::Ok
+        );
+        assert_eq!(*app.state.board.get(), start);
+    }
+
+    #[test]
+    fn repetition_draws() {
+        let mut app = joined_app();
+        let shuffle = ["Nf3", "Nf6", "Ng1", "Ng8"];
+
+        for chess_move in shuffle.iter().chain(&shuffle) {
+            assert_eq!(
+                execute(&mut app, Operation::ClaimDraw),
+                ChessResponse::Err(ChessError::NoDrawClaim)
+            );
+            assert_eq!(play_move(&mut app, chess_move), ChessResponse::Ok);
+        }
+
+        // threefold repetition only ends the game once claimed
+        assert_eq!(app.state.board.get().state, GameState::InPlay);
+        let mut claimed = joined_app();
+        claimed.state.board.set(app.state.board.get().clone());
+        assert_eq!(
+            execute(&mut claimed, Operation::ClaimDraw),
+            ChessResponse::Ok
+        );
+        assert_eq!(
+            claimed.state.board.get().result,
+            Some(GameResult::draw(Termination::Repetition))
+        );
+
+        // fivefold repetition ends it automatically
+        for chess_move in shuffle.iter().chain(&shuffle) {
+            assert_eq!(app.state.board.get().state, GameState::InPlay);
+            assert_eq!(play_move(&mut app, chess_move), ChessResponse::Ok);
+        }
+        assert_eq!(app.state.board.get().state, GameState::Draw);
+    }
+
+    #[test]
+    #[should_panic(expected = "Player not found")]
+    fn only_players_claim_draws() {
+        let mut app = joined_app();
+        for chess_move in ["Nf3", "Nf6", "Ng1", "Ng8"].repeat(2) {
+            assert_eq!(play_move(&mut app, chess_move), ChessResponse::Ok);
+        }
+
+        app.runtime
+            .set_authenticated_signer(Some(Owner::from(PublicKey::test_key(9))));
+        execute(&mut app, Operation::ClaimDraw);
+    }
+
+    #[test]
+    fn start_message_sets_up_the_game() {
+        let players = players();
+
+        for (color, expected) in [
+            (ColorPreference::White, Some(Color::White)),
+            (ColorPreference::Black, Some(Color::Black)),
+            (ColorPreference::Random, None),
+        ] {
+            let app = game_chain_app(color);
+
+            let first = Owner::from(players[0]);
+            let second = Owner::from(players[1]);
+            assert_eq!(*app.state.get_players(), vec![first, second]);
+            let first_color = app
+                .state
+                .owners
+                .get(&first)
+                .blocking_wait()
+                .unwrap()
+                .unwrap();
+            let second_color = app.state.owners.get(&second).blocking_wait().unwrap();
+            assert_eq!(second_color, Some(first_color.opposite()));
+            if let Some(expected) = expected {
+                assert_eq!(first_color, expected);
+            }
+
+            assert_eq!(
+                app.state.board.get().to_fen(),
+                Game::default().new().to_fen()
+            );
+            let clock = app.state.clock.get();
+            assert_eq!(clock.time_control.to_pgn(), "300+3");
+            assert_eq!(clock.time_left, [TimeDelta::from_secs(300); 2]);
+            assert_eq!(*app.state.started_at.get(), 100000000.into());
+        }
+    }
+
+    #[test]
+    fn start_message_is_only_taken_once_from_the_main_chain() {
+        let start = Message::Start {
+            players: players(),
+            time_control: time_control(60, 0),
+            color: ColorPreference::White,
+            block_delay: TimeDelta::from_secs(1),
+        };
 
-        response
+        let mut app = chain_app(ChainId::root(1));
+        receive(&mut app, ChainId::root(2), start.clone());
+        assert!(app.state.get_players().is_empty());
+
+        // a replayed start does not reset the game in progress
+        let mut app = game_chain_app(ColorPreference::White);
+        assert_eq!(play_move(&mut app, "e4"), ChessResponse::Ok);
+        receive(&mut app, ChainId::root(0), start);
+        assert_eq!(app.state.board.get().history.len(), 1);
+        assert_eq!(app.state.clock.get().time_control.to_pgn(), "300+3");
     }
 
-    fn create_and_instantiate_app(
-        initial_value: InstantiationArgument,
-        authentic_signer: Owner,
-    ) -> ChessContract {
-        let mut runtime = ContractRuntime::new().with_application_parameters(());
-        runtime.set_system_time(100000000.into());
-        runtime.set_authenticated_signer(authentic_signer);
-        let mut contract = ChessContract {
-            state: Chess::load(runtime.root_view_storage_context())
+    #[test]
+    fn game_over_updates_stats() {
+        let players = players();
+        let [white, black] = players.map(Owner::from);
+        let mut app = main_chain_app();
+        let message_id = MessageId {
+            chain_id: ChainId::root(0),
+            height: BlockHeight(0),
+            index: 0,
+        };
+        for public_key in &players {
+            for chain_id in [ChainId::root(1), ChainId::root(3)] {
+                app.state
+                    .game_chains
+                    .get_mut_or_default(public_key)
+                    .blocking_wait()
+                    .unwrap()
+                    .insert(GameChain {
+                        message_id,
+                        chain_id,
+                        rated: true,
+                    });
+            }
+        }
+        let game_over = |result| Message::GameOver {
+            result,
+            players,
+            pgn_hash: pgn::hash(""),
+        };
+
+        // results from other chains are ignored
+        app.runtime.set_message_id(MessageId {
+            chain_id: ChainId::root(2),
+            ..message_id
+        });
+        app.execute_message(game_over(GameResult::win(
+            Color::White,
+            Termination::Checkmate,
+        )))
+        .blocking_wait();
+        assert!(app.state.get_leaderboard().is_empty());
+
+        app.runtime.set_message_id(MessageId {
+            chain_id: ChainId::root(1),
+            ..message_id
+        });
+        app.execute_message(game_over(GameResult::win(
+            Color::White,
+            Termination::Checkmate,
+        )))
+        .blocking_wait();
+        // a finished game chain is forgotten after its first report
+        app.execute_message(game_over(GameResult::draw(Termination::Agreement)))
+            .blocking_wait();
+        // aborted games do not count
+        app.runtime.set_message_id(MessageId {
+            chain_id: ChainId::root(3),
+            ..message_id
+        });
+        app.execute_message(game_over(GameResult::no_result(Termination::Abandoned)))
+            .blocking_wait();
+
+        let white_stats = app.state.player_stats(&white).blocking_wait();
+        assert_eq!(
+            (
+                white_stats.games_played,
+                white_stats.wins,
+                white_stats.draws
+            ),
+            (1, 1, 0)
+        );
+        let black_stats = app.state.player_stats(&black).blocking_wait();
+        assert_eq!(
+            (
+                black_stats.games_played,
+                black_stats.losses,
+                black_stats.draws
+            ),
+            (1, 1, 0)
+        );
+
+        let leaderboard = app.state.get_leaderboard();
+        assert_eq!(leaderboard.len(), 2);
+        assert_eq!(leaderboard[0].player_id, white.to_string());
+
+        for public_key in &players {
+            assert!(app
+                .state
+                .game_chains
+                .get(public_key)
                 .blocking_wait()
-                .expect("Failed to read from mock key value store"),
-            runtime,
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn seeks() {
+        let [seeker, acceptor] = players();
+        let mut app = main_chain_app();
+        let post_seek = Operation::PostSeek {
+            player: seeker,
+            time_control: TimeControl::default(),
+            rated: false,
+            color_preference: ColorPreference::Black,
+            rating_range: Some(RatingRange {
+                min: 1400,
+                max: 1600,
+            }),
+        };
+
+        // seeks are posted with the signer's own key and a stake, and can be cancelled
+        app.runtime.set_authenticated_signer(Owner::from(acceptor));
+        assert_eq!(
+            execute(&mut app, post_seek.clone()),
+            ChessResponse::Err(ChessError::InvalidRequest)
+        );
+        app.runtime.set_authenticated_signer(Owner::from(seeker));
+        assert_eq!(execute(&mut app, post_seek.clone()), ChessResponse::Ok);
+        assert_eq!(
+            app.runtime.owner_balance(Owner::from(seeker)),
+            Amount::from_tokens(9)
+        );
+        assert_eq!(app.runtime.chain_balance(), Amount::ONE);
+        assert_eq!(execute(&mut app, Operation::CancelSeek), ChessResponse::Ok);
+        assert!(app.state.open_seeks().blocking_wait().is_empty());
+        assert_eq!(
+            app.runtime.owner_balance(Owner::from(seeker)),
+            Amount::from_tokens(10)
+        );
+        assert_eq!(app.runtime.chain_balance(), Amount::ZERO);
+
+        assert_eq!(execute(&mut app, post_seek), ChessResponse::Ok);
+        let seeks = app.state.open_seeks().blocking_wait();
+        assert_eq!(seeks.len(), 1);
+        let seek_id = seeks[0].seek_id;
+        assert_eq!(seeks[0].player, seeker);
+        assert_eq!(seeks[0].rating_range.unwrap().max, 1600);
+
+        // nobody can accept their own seek, with their own key or another one
+        for player in [seeker, acceptor] {
+            assert_eq!(
+                execute(&mut app, Operation::AcceptSeek { seek_id, player }),
+                ChessResponse::Err(ChessError::InvalidRequest)
+            );
+        }
+
+        app.runtime.set_authenticated_signer(Owner::from(acceptor));
+        assert_eq!(
+            execute(
+                &mut app,
+                Operation::AcceptSeek {
+                    seek_id: seek_id + 1,
+                    player: acceptor
+                }
+            ),
+            ChessResponse::Err(ChessError::SeekNotFound)
+        );
+        let accept_seek = Operation::AcceptSeek {
+            seek_id,
+            player: acceptor,
+        };
+
+        // the acceptor's rating has to be within the range of the seek
+        let mut stats = PlayerStats::new(acceptor.to_string());
+        stats.record_win();
+        let owner = Owner::from(acceptor);
+        app.state.stats.insert(&owner, stats.clone()).unwrap();
+        assert_eq!(
+            execute(&mut app, accept_seek.clone()),
+            ChessResponse::Err(ChessError::RatingOutOfRange)
+        );
+        stats.record_loss();
+        app.state.stats.insert(&owner, stats).unwrap();
+
+        // the acceptor stakes as well
+        app.runtime
+            .set_owner_balance(Owner::from(acceptor), Amount::ZERO);
+        assert_eq!(
+            execute(&mut app, accept_seek.clone()),
+            ChessResponse::Err(ChessError::InsufficientBalance)
+        );
+        app.runtime
+            .set_owner_balance(Owner::from(acceptor), Amount::from_tokens(10));
+        expect_open_chain(&mut app, [seeker, acceptor]);
+        assert_eq!(execute(&mut app, accept_seek), ChessResponse::Ok);
+        assert!(app.state.open_seeks().blocking_wait().is_empty());
+        assert_eq!(
+            app.runtime.owner_balance(Owner::from(acceptor)),
+            Amount::from_tokens(9)
+        );
+
+        let game_chains = app
+            .state
+            .game_chains
+            .get(&acceptor)
+            .blocking_wait()
+            .unwrap();
+        assert!(game_chains.is_some_and(|chains| chains.iter().all(|game| !game.rated)));
+        assert!(matches!(
+            app.runtime.created_send_message_requests().last().unwrap().message,
+            Message::Start { players, color: ColorPreference::Black, .. }
+                if players == [seeker, acceptor]
+        ));
+    }
+
+    #[test]
+    fn challenges() {
+        let [challenger, opponent] = players();
+        let [challenger_chain, opponent_chain] = [ChainId::root(2), ChainId::root(3)];
+
+        // the challenger's chain asks the main chain to record the challenge
+        let mut challenger_app = chain_app(challenger_chain);
+        challenger_app
+            .runtime
+            .set_authenticated_signer(Owner::from(challenger));
+        let challenge = Operation::Challenge {
+            player: challenger,
+            opponent,
+            opponent_chain,
+            time_control: TimeControl::default(),
+            color: ColorPreference::White,
+        };
+        assert_eq!(execute(&mut challenger_app, challenge), ChessResponse::Ok);
+        let request = challenger_app
+            .runtime
+            .created_send_message_requests()
+            .pop()
+            .unwrap();
+        assert_eq!(request.destination, Destination::from(ChainId::root(0)));
+        assert!(request.authenticated);
+
+        // the main chain records challenges signed by the challenger only
+        let mut main_app = main_chain_app();
+        main_app
+            .runtime
+            .set_authenticated_signer(Owner::from(opponent));
+        receive(&mut main_app, challenger_chain, request.message.clone());
+        assert!(main_app.runtime.created_send_message_requests().is_empty());
+        main_app
+            .runtime
+            .set_authenticated_signer(Owner::from(challenger));
+        for _ in 0..2 {
+            receive(&mut main_app, challenger_chain, request.message.clone());
+        }
+        let requests = main_app.runtime.created_send_message_requests().clone();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].destination, Destination::from(opponent_chain));
+        assert_eq!(requests[1].destination, Destination::from(challenger_chain));
+
+        // the opponent's chain only takes challenges from the main chain
+        let mut opponent_app = chain_app(opponent_chain);
+        opponent_app
+            .runtime
+            .set_authenticated_signer(Owner::from(opponent));
+        receive(
+            &mut opponent_app,
+            challenger_chain,
+            requests[0].message.clone(),
+        );
+        assert!(opponent_app
+            .state
+            .open_challenges()
+            .blocking_wait()
+            .is_empty());
+        for request in [&requests[0], &requests[2]] {
+            receive(&mut opponent_app, ChainId::root(0), request.message.clone());
+        }
+        let challenges = opponent_app.state.open_challenges().blocking_wait();
+        assert_eq!(challenges.len(), 2);
+        assert_eq!(challenges[0].challenger, challenger);
+        assert_eq!(challenges[0].challenger_chain, challenger_chain);
+        assert_eq!(challenges[0].opponent, opponent);
+
+        // the opponent declines one challenge and accepts the other
+        assert_eq!(
+            execute(
+                &mut opponent_app,
+                Operation::DeclineChallenge { challenge_id: 2 }
+            ),
+            ChessResponse::Err(ChessError::ChallengeNotFound)
+        );
+        assert_eq!(
+            execute(
+                &mut opponent_app,
+                Operation::DeclineChallenge { challenge_id: 0 }
+            ),
+            ChessResponse::Ok
+        );
+        let accept = Operation::AcceptChallenge { challenge_id: 1 };
+        opponent_app
+            .runtime
+            .set_authenticated_signer(Owner::from(challenger));
+        assert_eq!(
+            execute(&mut opponent_app, accept.clone()),
+            ChessResponse::Err(ChessError::InvalidRequest)
+        );
+        opponent_app
+            .runtime
+            .set_authenticated_signer(Owner::from(opponent));
+        assert_eq!(
+            execute(&mut opponent_app, accept.clone()),
+            ChessResponse::Ok
+        );
+        assert_eq!(
+            execute(&mut opponent_app, accept),
+            ChessResponse::Err(ChessError::ChallengeNotFound)
+        );
+        assert!(opponent_app
+            .state
+            .open_challenges()
+            .blocking_wait()
+            .is_empty());
+        let request = opponent_app
+            .runtime
+            .created_send_message_requests()
+            .pop()
+            .unwrap();
+        assert_eq!(request.destination, Destination::from(ChainId::root(0)));
+        assert!(request.authenticated);
+
+        // the main chain refunds acceptances from any other chain
+        main_app
+            .runtime
+            .set_authenticated_signer(Owner::from(opponent));
+        main_app.runtime.set_chain_balance(Amount::from_tokens(1));
+        receive(&mut main_app, challenger_chain, request.message.clone());
+        assert_eq!(main_app.runtime.created_send_message_requests().len(), 4);
+        let refund = Account {
+            chain_id: challenger_chain,
+            owner: Some(Owner::from(opponent)),
+        };
+        assert_eq!(main_app.runtime.outgoing_transfers()[&refund], PLAYER_STAKE);
+
+        // and starts the game of an acceptance from the opponent's chain only once
+        expect_open_chain(&mut main_app, [challenger, opponent]);
+        main_app.runtime.set_chain_balance(Amount::from_tokens(3));
+        for _ in 0..2 {
+            receive(&mut main_app, opponent_chain, request.message.clone());
+        }
+        assert!(main_app
+            .state
+            .game_chains
+            .get(&challenger)
+            .blocking_wait()
+            .unwrap()
+            .is_some());
+        let requests = main_app.runtime.created_send_message_requests().clone();
+        assert_eq!(requests.len(), 6);
+        assert!(matches!(
+            requests[5].message,
+            Message::Start { players, color: ColorPreference::White, .. }
+                if players == [challenger, opponent]
+        ));
+
+        // the challenger's chain forgets the challenge once the main chain closes it
+        receive(
+            &mut challenger_app,
+            ChainId::root(0),
+            requests[3].message.clone(),
+        );
+        assert_eq!(
+            challenger_app.state.open_challenges().blocking_wait().len(),
+            1
+        );
+        assert_eq!(requests[4].destination, Destination::from(challenger_chain));
+        receive(
+            &mut challenger_app,
+            ChainId::root(0),
+            requests[4].message.clone(),
+        );
+        assert!(challenger_app
+            .state
+            .open_challenges()
+            .blocking_wait()
+            .is_empty());
+    }
+
+    #[test]
+    fn challenges_can_be_declined_or_cancelled() {
+        let [challenger, opponent] = players();
+        let [challenger_chain, opponent_chain] = [ChainId::root(2), ChainId::root(3)];
+        let challenger_account = Account {
+            chain_id: challenger_chain,
+            owner: Some(Owner::from(challenger)),
+        };
+
+        // the challenger's chain sends every challenge on, along with its stake
+        let mut challenger_app = chain_app(challenger_chain);
+        challenger_app
+            .runtime
+            .set_authenticated_signer(Owner::from(challenger));
+        let challenge = Operation::Challenge {
+            player: challenger,
+            opponent,
+            opponent_chain,
+            time_control: TimeControl::default(),
+            color: ColorPreference::Random,
+        };
+        for _ in 0..=MAX_OPEN_CHALLENGES {
+            assert_eq!(
+                execute(&mut challenger_app, challenge.clone()),
+                ChessResponse::Ok
+            );
+        }
+        let issued = challenger_app
+            .runtime
+            .created_send_message_requests()
+            .clone();
+        assert_eq!(issued.len(), MAX_OPEN_CHALLENGES + 1);
+
+        // the main chain counts the challenges it recorded and refunds the ones above the limit
+        let mut main_app = main_chain_app();
+        main_app.runtime.set_chain_balance(Amount::from_tokens(10));
+        main_app
+            .runtime
+            .set_authenticated_signer(Owner::from(challenger));
+        for request in issued {
+            receive(&mut main_app, challenger_chain, request.message);
+        }
+        let requests = main_app.runtime.created_send_message_requests().clone();
+        assert_eq!(requests.len(), 2 * MAX_OPEN_CHALLENGES);
+        assert_eq!(
+            main_app.runtime.outgoing_transfers()[&challenger_account],
+            PLAYER_STAKE
+        );
+        for request in requests.iter().skip(1).step_by(2) {
+            receive(
+                &mut challenger_app,
+                ChainId::root(0),
+                request.message.clone(),
+            );
+        }
+
+        // only the opponent declines, only the challenger cancels
+        let mut opponent_app = chain_app(opponent_chain);
+        for request in requests.iter().step_by(2) {
+            receive(&mut opponent_app, ChainId::root(0), request.message.clone());
+        }
+        let decline = Operation::DeclineChallenge { challenge_id: 0 };
+        let cancel = Operation::CancelChallenge { challenge_id: 1 };
+        opponent_app
+            .runtime
+            .set_authenticated_signer(Owner::from(challenger));
+        assert_eq!(
+            execute(&mut opponent_app, decline.clone()),
+            ChessResponse::Err(ChessError::InvalidRequest)
+        );
+        opponent_app
+            .runtime
+            .set_authenticated_signer(Owner::from(opponent));
+        assert_eq!(execute(&mut opponent_app, decline), ChessResponse::Ok);
+        assert_eq!(
+            execute(&mut opponent_app, cancel.clone()),
+            ChessResponse::Err(ChessError::InvalidRequest)
+        );
+        assert_eq!(execute(&mut challenger_app, cancel), ChessResponse::Ok);
+        let declined = opponent_app
+            .runtime
+            .created_send_message_requests()
+            .pop()
+            .unwrap();
+        let cancelled = challenger_app
+            .runtime
+            .created_send_message_requests()
+            .pop()
+            .unwrap();
+        assert!(declined.authenticated && cancelled.authenticated);
+
+        // the main chain only takes answers signed by the right player on their chain
+        receive(&mut main_app, opponent_chain, declined.message.clone());
+        receive(&mut main_app, opponent_chain, cancelled.message.clone());
+        assert_eq!(
+            main_app.runtime.created_send_message_requests().len(),
+            2 * MAX_OPEN_CHALLENGES
+        );
+
+        main_app
+            .runtime
+            .set_authenticated_signer(Owner::from(opponent));
+        receive(&mut main_app, opponent_chain, declined.message);
+        main_app
+            .runtime
+            .set_authenticated_signer(Owner::from(challenger));
+        receive(&mut main_app, challenger_chain, cancelled.message);
+        assert_eq!(
+            main_app.runtime.outgoing_transfers()[&challenger_account],
+            PLAYER_STAKE.saturating_mul(3)
+        );
+        let requests = main_app.runtime.created_send_message_requests().clone();
+        let [.., to_challenger, to_opponent] = &requests[..] else {
+            panic!("No notifications sent");
         };
+        assert_eq!(
+            to_challenger.destination,
+            Destination::from(challenger_chain)
+        );
+        assert_eq!(to_opponent.destination, Destination::from(opponent_chain));
+
+        // which closes the challenges on the players' chains
+        receive(
+            &mut challenger_app,
+            ChainId::root(0),
+            to_challenger.message.clone(),
+        );
+        receive(
+            &mut opponent_app,
+            ChainId::root(0),
+            to_opponent.message.clone(),
+        );
+        for app in [&challenger_app, &opponent_app] {
+            let challenges = app.state.open_challenges().blocking_wait();
+            assert_eq!(challenges.len(), MAX_OPEN_CHALLENGES - 2);
+        }
+    }
+
+    /// The players, apps and helpers shared by the contract tests
+    mod fixtures {
+        use super::*;
 
-        contract
-            .instantiate(initial_value)
-            .now_or_never()
-            .expect("Initialization of application state should not await anything");
+        /// The keys of the two players of the tests
+        pub fn players() -> [PublicKey; 2] {
+            [PublicKey::test_key(1), PublicKey::test_key(2)]
+        }
+
+        /// The owners of the two players of the tests
+        pub fn owners() -> [Owner; 2] {
+            players().map(Owner::from)
+        }
+
+        /// A Fischer time control in seconds
+        pub fn time_control(start_time: u64, increment: u64) -> TimeControl {
+            TimeControl {
+                start_time: TimeDelta::from_secs(start_time),
+                increment: TimeDelta::from_secs(increment),
+                mode: TimeMode::Fischer,
+            }
+        }
+
+        /// An app on the given chain, the main chain is `ChainId::root(0)`
+        pub fn chain_app(chain_id: ChainId) -> ChessContract {
+            let mut runtime = ContractRuntime::new().with_application_parameters(());
+            runtime.set_chain_id(chain_id);
+            runtime.set_application_creator_chain_id(ChainId::root(0));
+            runtime.set_application_id(ApplicationId::default().with_abi());
+            runtime.set_system_time(100000000.into());
+            runtime.set_chain_balance(Amount::ZERO);
+            runtime.set_owner_balances(owners().map(|owner| (owner, Amount::from_tokens(10))));
+            ChessContract {
+                state: Chess::load(runtime.root_view_storage_context())
+                    .blocking_wait()
+                    .expect("Failed to read from mock key value store"),
+                runtime,
+            }
+        }
+
+        /// An app on the main chain, where games are started
+        pub fn main_chain_app() -> ChessContract {
+            chain_app(ChainId::root(0))
+        }
+
+        /// An app on the main chain instantiated with `argument`, the signer is the first player
+        pub fn instantiated_app(argument: InstantiationArgument) -> ChessContract {
+            let mut app = main_chain_app();
+            app.runtime.set_authenticated_signer(owners()[0]);
+            app.instantiate(argument).blocking_wait();
+            app
+        }
+
+        /// An app where both players joined, the signer plays White
+        pub fn joined_app() -> ChessContract {
+            let mut app = instantiated_app(InstantiationArgument {
+                players: owners(),
+                time_control: time_control(600, 0),
+                block_delay: 100000000.into(),
+            });
+            for player in owners() {
+                assert_eq!(
+                    execute(&mut app, Operation::NewGame { player }),
+                    ChessResponse::Ok
+                );
+            }
+            app
+        }
+
+        /// An app on a temporary game chain, set up by the main chain, the signer is the first
+        /// player
+        pub fn game_chain_app(color: ColorPreference) -> ChessContract {
+            let mut app = chain_app(ChainId::root(1));
+            app.runtime.set_authenticated_signer(owners()[0]);
+            app.runtime.set_chain_balance(Amount::from_tokens(1));
+            app.runtime.set_can_close_chain(true);
+            app.runtime.set_message_id(MessageId {
+                chain_id: ChainId::root(0),
+                height: BlockHeight(0),
+                index: 0,
+            });
+
+            app.execute_message(Message::Start {
+                players: players(),
+                time_control: time_control(300, 3),
+                color,
+                block_delay: TimeDelta::from_secs(1),
+            })
+            .blocking_wait();
+            app
+        }
+
+        /// Expects the main chain to open a game chain for the players, funded with both stakes
+        pub fn expect_open_chain(app: &mut ChessContract, players: [PublicKey; 2]) {
+            let ownership = ChainOwnership::multiple(
+                [(players[0], 100), (players[1], 100)],
+                100,
+                TimeoutConfig::default(),
+            );
+            let permissions = ApplicationPermissions::new_single(ApplicationId::default());
+            let message_id = MessageId {
+                chain_id: ChainId::root(0),
+                height: BlockHeight(1),
+                index: 0,
+            };
+            app.runtime.add_expected_open_chain_call(
+                ownership,
+                permissions,
+                Amount::from_tokens(2),
+                message_id,
+            );
+        }
 
-        contract
+        pub fn execute(app: &mut ChessContract, operation: Operation) -> ChessResponse {
+            app.execute_operation(operation).blocking_wait()
+        }
+
+        /// Executes a message sent from the `origin` chain
+        pub fn receive(app: &mut ChessContract, origin: ChainId, message: Message) {
+            app.runtime.set_message_id(MessageId {
+                chain_id: origin,
+                height: BlockHeight(0),
+                index: 0,
+            });
+            app.execute_message(message).blocking_wait();
+        }
+
+        pub fn claim_victory_on_time(app: &mut ChessContract) -> ChessResponse {
+            execute(app, Operation::ClaimVictoryOnTime)
+        }
+
+        pub fn play_move(app: &mut ChessContract, chess_move: &str) -> ChessResponse {
+            execute(
+                app,
+                Operation::PlayMove {
+                    chess_move: chess_move.to_string(),
+                },
+            )
+        }
     }
 }
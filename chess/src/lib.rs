@@ -1,12 +1,13 @@
 #![allow(non_snake_case)]
 
-use std::collections::HashMap;
+use std::{collections::HashMap, str::FromStr};
 
-use async_graphql::{Enum, Request, Response, SimpleObject};
+use async_graphql::{Enum, InputObject, Request, Response, SimpleObject};
 use chessboard::ChessBoard;
 use lazy_static::lazy_static;
 use linera_sdk::base::{
-    Amount, ChainId, ContractAbi, MessageId, Owner, PublicKey, ServiceAbi, TimeDelta, Timestamp,
+    Amount, BcsHashable, ChainId, ContractAbi, CryptoHash, MessageId, Owner, PublicKey, ServiceAbi,
+    TimeDelta, Timestamp,
 };
 use piece::{Color, Piece};
 use serde::{Deserialize, Serialize};
@@ -24,6 +25,8 @@ use zobrist::{
     CASTLE_KEYS, EP_KEYS, PIECE_KEYS,
 };
 pub mod magic;
+pub mod perft;
+pub mod pgn;
 pub mod prng;
 pub mod zobrist;
 
@@ -42,16 +45,17 @@ impl ServiceAbi for ChessAbi {
 pub struct InstantiationArgument {
     /// The `Owner` controlling player 1 and 2, respectively.
     pub players: [Owner; 2],
-    /// The initial time each player has to think about their turns.
-    pub start_time: TimeDelta,
-    /// The duration that is added to the clock after each turn.
-    pub increment: TimeDelta,
+    /// The time control of the game.
+    pub time_control: TimeControl,
     /// The maximum time that is allowed to pass between a block proposal and validation.
     /// This should be long enough to confirm a block, but short enough for the block timestamp
     /// to accurately reflect the current time.
     pub block_delay: TimeDelta,
 }
 
+/// The rating of a player without games
+pub const INITIAL_RATING: u32 = 1500;
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, SimpleObject)]
 pub struct PlayerStats {
     pub player_id: String,
@@ -62,6 +66,50 @@ pub struct PlayerStats {
     pub win_rate: f32,
 }
 
+impl PlayerStats {
+    /// A function to create empty stats for a player
+    pub fn new(player_id: String) -> Self {
+        PlayerStats {
+            player_id,
+            ..Default::default()
+        }
+    }
+
+    /// A function to record a won game
+    pub fn record_win(&mut self) {
+        self.wins += 1;
+        self.record_game();
+    }
+
+    /// A function to record a lost game
+    pub fn record_loss(&mut self) {
+        self.losses += 1;
+        self.record_game();
+    }
+
+    /// A function to record a drawn game
+    pub fn record_draw(&mut self) {
+        self.draws += 1;
+        self.record_game();
+    }
+
+    /// A rating derived from the results, starting at 1500 and moving 400 points per net win
+    /// over the games played
+    pub fn rating(&self) -> u32 {
+        if self.games_played == 0 {
+            return INITIAL_RATING;
+        }
+        let net_wins = i64::from(self.wins) - i64::from(self.losses);
+        let rating = i64::from(INITIAL_RATING) + 400 * net_wins / i64::from(self.games_played);
+        rating.max(0) as u32
+    }
+
+    fn record_game(&mut self) {
+        self.games_played += 1;
+        self.win_rate = self.wins as f32 / self.games_played as f32;
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ChessResponse {
     Ok,
@@ -74,51 +122,131 @@ pub enum Operation {
     NewGame {
         player: Owner,
     },
-    MakeMove {
-        from: String,
-        to: String,
-        piece: String,
-    },
-    CapturePiece {
-        // square which captures
-        from: String,
-        // square which is being captured
-        to: String,
-        // piece which captures
-        piece: String,
-        // piece which is being captured
-        captured_piece: String,
-    },
-    PawnPromotion {
-        from: String,
-        to: String,
-        piece: String,
-        promoted_piece: String,
+    /// Play a move given in UCI (`e2e4`, `e7e8q`) or SAN (`Nf3`, `exd8=Q+`, `O-O`). The piece,
+    /// capture, en passant, castle or promotion is derived from the board.
+    PlayMove {
+        chess_move: String,
     },
     Resign,
+    /// Abort the game before both players have made their first move, without affecting the
+    /// player stats
+    Abort,
+    /// Claim the game when the opponent, who is to move, has run out of time. It is drawn if the
+    /// claimant has no mating material left.
+    ClaimVictoryOnTime,
+    /// Offer a draw to the opponent, the offer expires when the opponent moves
+    OfferDraw,
+    /// Claim a draw by threefold repetition or the 50-move rule
+    ClaimDraw,
+    /// Accept the draw offered by the opponent
+    AcceptDraw,
+    /// Decline the draw offered by the opponent
+    DeclineDraw,
+    /// Ask the opponent to take back the last `plies` moves, the request expires with the next move
+    RequestTakeback {
+        plies: u32,
+    },
+    /// Accept the takeback requested by the opponent
+    AcceptTakeback,
     /// Start the game on a temporary chain
     StartGame {
         /// The `Owner` controlling player 1 and 2, respectively.
         players: [PublicKey; 2],
         /// A small amount to cover the fees for the game, on the new chain
         amount: Amount,
-        /// Game's total time (~15 mins)
-        match_time: TimeDelta,
+        /// The time control of the game
+        time_control: TimeControl,
+        /// The color of the first player
+        color: ColorPreference,
+    },
+    /// Post a seek to the lobby of the main chain, `player` is the public key of the signer. The
+    /// signer stakes towards the fees of the game chain from their account on the main chain.
+    PostSeek {
+        player: PublicKey,
+        time_control: TimeControl,
+        /// Whether the game counts towards the player stats
+        rated: bool,
+        /// The color of the player posting the seek
+        color_preference: ColorPreference,
+        /// The ratings accepted for the opponent
+        rating_range: Option<RatingRange>,
+    },
+    /// Cancel the open seeks of the signer, refunding their stakes
+    CancelSeek,
+    /// Accept an open seek of another player, staking like the poster and starting the game on a
+    /// new temporary chain. `player` is the public key of the signer: the game chain is owned by
+    /// both players' keys, and the signer only identifies the key by its hash.
+    AcceptSeek {
+        seek_id: u64,
+        player: PublicKey,
+    },
+    /// Challenge `opponent` on their chain through the main chain, which keeps track of the
+    /// challenge until it is answered. `player` is the public key of the signer, who stakes
+    /// the same amount as in the lobby.
+    Challenge {
+        player: PublicKey,
+        opponent: PublicKey,
+        opponent_chain: ChainId,
+        time_control: TimeControl,
+        /// The color of the challenger
+        color: ColorPreference,
+    },
+    /// Accept a challenge received on this chain, starting the game on a new temporary chain.
+    /// Only the challenged player can accept it.
+    AcceptChallenge {
+        challenge_id: u64,
+    },
+    /// Decline a challenge received on this chain, the challenger gets their stake back.
+    /// Only the challenged player can decline it.
+    DeclineChallenge {
+        challenge_id: u64,
+    },
+    /// Cancel a challenge sent from this chain that was not answered yet, refunding the stake
+    CancelChallenge {
+        challenge_id: u64,
     },
 }
-//     /// The `Owner` controlling player 1 and 2, respectively.
-//     pub players: [Owner; 2],
-//     /// The initial time each player has to think about their turns.
-//     pub start_time: TimeDelta,
-//
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub enum Message {
     Start {
         players: [PublicKey; 2],
-        /// Represents the total amount of time for each player
-        timer: TimeDelta,
+        /// The time control of the game
+        time_control: TimeControl,
+        /// The color of the first player
+        color: ColorPreference,
+        /// The block delay of the main chain's clock
+        block_delay: TimeDelta,
+    },
+    /// Reports the result of a finished game from its temporary chain to the main chain
+    GameOver {
+        result: GameResult,
+        /// The White and Black player, respectively
+        players: [PublicKey; 2],
+        /// The hash of the game's PGN
+        pgn_hash: CryptoHash,
+    },
+    /// Asks the main chain to record a challenge, sent by the challenger's chain
+    IssueChallenge {
+        challenger: PublicKey,
+        opponent: PublicKey,
+        opponent_chain: ChainId,
+        time_control: TimeControl,
+        /// The color of the challenger
+        color: ColorPreference,
     },
+    /// A challenge recorded by the main chain, sent to the chains of both players
+    Challenge { challenge: Challenge },
+    /// Asks the main chain to start the game of an accepted challenge, sent by the opponent's
+    /// chain
+    ChallengeAccepted { challenge_id: u64 },
+    /// Asks the main chain to refund the challenger, sent by the opponent's chain
+    ChallengeDeclined { challenge_id: u64 },
+    /// Asks the main chain to refund the challenger, sent by the challenger's chain
+    ChallengeCancelled { challenge_id: u64 },
+    /// Lets a player's chain know that the main chain closed a challenge, which tells the
+    /// challenger that it was declined
+    ChallengeClosed { challenge_id: u64 },
 }
 
 /// The IDs of a temporary chain for a single game.
@@ -128,6 +256,55 @@ pub struct GameChain {
     pub message_id: MessageId,
     /// The ID of the temporary game chain itself.
     pub chain_id: ChainId,
+    /// Whether the game counts towards the player stats.
+    pub rated: bool,
+}
+
+/// A challenge between players on two chains, recorded by the main chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct Challenge {
+    pub challenge_id: u64,
+    /// The player sending the challenge
+    pub challenger: PublicKey,
+    /// The chain the challenge was sent from
+    pub challenger_chain: ChainId,
+    /// The player being challenged
+    pub opponent: PublicKey,
+    /// The chain the challenge was sent to
+    pub opponent_chain: ChainId,
+    pub time_control: TimeControl,
+    /// The color of the challenger
+    pub color: ColorPreference,
+}
+
+/// A range of ratings an opponent must be within to accept a seek
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject, InputObject)]
+#[graphql(input_name = "RatingRangeInput")]
+pub struct RatingRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl RatingRange {
+    /// Whether the rating is within the range, bounds included
+    pub fn contains(&self, rating: u32) -> bool {
+        (self.min..=self.max).contains(&rating)
+    }
+}
+
+/// An open request for a game in the lobby of the main chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct Seek {
+    pub seek_id: u64,
+    /// The player looking for a game
+    pub player: PublicKey,
+    pub time_control: TimeControl,
+    /// Whether the game counts towards the player stats
+    pub rated: bool,
+    /// The color of the player posting the seek
+    pub color_preference: ColorPreference,
+    /// The ratings accepted for the opponent, checked against `PlayerStats::rating`
+    pub rating_range: Option<RatingRange>,
 }
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Enum)]
@@ -139,6 +316,197 @@ pub enum GameState {
     Stalemate,
     Draw,
     Resign,
+    /// The player to move ran out of time
+    Timeout,
+    /// Neither player has enough material left to checkmate
+    InsufficientMaterial,
+    /// The game was abandoned before it was finished
+    Abandoned,
+}
+
+/// The outcome of a finished game
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum Outcome {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    /// The game stopped without a result, e.g. it was aborted
+    NoResult,
+}
+
+/// Why a game ended
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum Termination {
+    Checkmate,
+    Resignation,
+    Timeout,
+    Stalemate,
+    /// Both players agreed to a draw
+    Agreement,
+    /// Threefold or fivefold repetition
+    Repetition,
+    /// 50-move or 75-move rule
+    FiftyMove,
+    InsufficientMaterial,
+    Abandoned,
+}
+
+/// The result of a finished game, with the reason it ended
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct GameResult {
+    pub outcome: Outcome,
+    pub reason: Termination,
+}
+
+impl GameResult {
+    /// A function to create the result of a game won by `winner`
+    pub fn win(winner: Color, reason: Termination) -> Self {
+        let outcome = match winner {
+            Color::White => Outcome::WhiteWins,
+            Color::Black => Outcome::BlackWins,
+        };
+        GameResult { outcome, reason }
+    }
+
+    /// A function to create the result of a drawn game
+    pub fn draw(reason: Termination) -> Self {
+        GameResult {
+            outcome: Outcome::Draw,
+            reason,
+        }
+    }
+
+    /// A function to create the result of a game that stopped without a winner or a draw
+    pub fn no_result(reason: Termination) -> Self {
+        GameResult {
+            outcome: Outcome::NoResult,
+            reason,
+        }
+    }
+
+    /// Returns the color of the winner, `None` for a draw or no result
+    pub fn winner(&self) -> Option<Color> {
+        match self.outcome {
+            Outcome::WhiteWins => Some(Color::White),
+            Outcome::BlackWins => Some(Color::Black),
+            Outcome::Draw | Outcome::NoResult => None,
+        }
+    }
+
+    /// Returns the color of the loser, `None` for a draw or no result
+    pub fn loser(&self) -> Option<Color> {
+        self.winner().map(|winner| winner.opposite())
+    }
+
+    /// A function to get the PGN result token (`1-0`, `0-1`, `1/2-1/2` or `*`)
+    pub fn to_pgn(&self) -> &'static str {
+        match self.outcome {
+            Outcome::WhiteWins => "1-0",
+            Outcome::BlackWins => "0-1",
+            Outcome::Draw => "1/2-1/2",
+            Outcome::NoResult => "*",
+        }
+    }
+}
+
+/// A pending request to take back moves
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct TakebackRequest {
+    /// The player asking for the takeback
+    pub requester: Color,
+    /// The number of half-moves to take back
+    pub plies: u32,
+}
+
+/// The color requested by the first player of a new game
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum ColorPreference {
+    /// The colors are drawn at random
+    #[default]
+    Random,
+    /// The first player plays White, keeping the order of the players fixed
+    White,
+    /// The first player plays Black
+    Black,
+}
+
+impl ColorPreference {
+    /// Returns the colors of the first and second player, `coin` decides random assignments
+    pub fn assign(&self, coin: bool) -> [Color; 2] {
+        match (self, coin) {
+            (ColorPreference::White, _) | (ColorPreference::Random, false) => {
+                [Color::White, Color::Black]
+            }
+            (ColorPreference::Black, _) | (ColorPreference::Random, true) => {
+                [Color::Black, Color::White]
+            }
+        }
+    }
+
+    /// The coin for random colors, drawn from a hash of both players' keys and the game chain. No
+    /// block proposer can steer it, though a player creating keys until one wins could.
+    pub fn coin(players: [PublicKey; 2], chain_id: ChainId) -> bool {
+        CryptoHash::new(&ColorDraw { players, chain_id }).as_bytes()[0] & 1 == 1
+    }
+}
+
+/// The inputs of the coin for random colors
+#[derive(Serialize, Deserialize)]
+struct ColorDraw {
+    players: [PublicKey; 2],
+    chain_id: ChainId,
+}
+
+impl BcsHashable for ColorDraw {}
+
+/// How the increment of a time control is applied
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum TimeMode {
+    /// The increment is added to the clock after each move
+    #[default]
+    Fischer,
+    /// The time used for a move, up to the increment, is given back after the move
+    Bronstein,
+    /// The clock only starts running once the increment has passed
+    SimpleDelay,
+}
+
+/// The time control of a game
+#[derive(
+    Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject, InputObject,
+)]
+#[graphql(input_name = "TimeControlInput")]
+#[serde(rename_all = "camelCase")]
+pub struct TimeControl {
+    /// The initial time each player has to think about their turns.
+    pub start_time: TimeDelta,
+    /// The Fischer increment, or the delay in the delay modes.
+    pub increment: TimeDelta,
+    /// How the increment is applied.
+    #[serde(default)]
+    pub mode: TimeMode,
+}
+
+impl TimeControl {
+    /// A function to get the PGN `TimeControl` tag value in seconds, `900+5` with a Fischer
+    /// increment and just the base time with a delay
+    pub fn to_pgn(&self) -> String {
+        let start_time = self.start_time.as_micros() / 1_000_000;
+        match self.mode {
+            TimeMode::Fischer => {
+                format!("{}+{}", start_time, self.increment.as_micros() / 1_000_000)
+            }
+            TimeMode::Bronstein | TimeMode::SimpleDelay => start_time.to_string(),
+        }
+    }
+
+    /// The part of the time spent on a move that is taken from the clock
+    pub fn charged_time(&self, elapsed: TimeDelta) -> TimeDelta {
+        match self.mode {
+            TimeMode::Fischer => elapsed,
+            TimeMode::Bronstein | TimeMode::SimpleDelay => elapsed.saturating_sub(self.increment),
+        }
+    }
 }
 
 #[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
@@ -173,10 +541,63 @@ pub enum ChessError {
     Checkmate,
     #[error("Stalemate")]
     Stalemate,
+    #[error("Invalid move notation")]
+    InvalidNotation,
+    #[error("Ambiguous move")]
+    AmbiguousMove,
+    #[error("Player has not timed out")]
+    NotTimedOut,
+    #[error("No draw offer from the opponent")]
+    NoDrawOffer,
+    #[error("No threefold repetition or 50-move rule to claim")]
+    NoDrawClaim,
+    #[error("No takeback request from the opponent")]
+    NoTakebackRequest,
+    #[error("Game can only be aborted before both players have moved")]
+    AbortNotAllowed,
+    #[error("Seek not found")]
+    SeekNotFound,
+    #[error("Challenge not found")]
+    ChallengeNotFound,
+    #[error("Players of the game not found")]
+    PlayersNotFound,
+    #[error("Insufficient balance")]
+    InsufficientBalance,
+    #[error("Rating outside the range of the seek")]
+    RatingOutOfRange,
 }
 
 pub type Result<T> = std::result::Result<T, ChessError>;
 
+/// Errors reported when parsing a FEN string
+#[derive(Debug, PartialEq, Serialize, Deserialize, Eq, Clone, Error)]
+pub enum FenError {
+    #[error("Expected 6 FEN fields, found {0}")]
+    FieldCount(usize),
+    #[error("Expected 8 ranks, found {0}")]
+    RankCount(usize),
+    #[error("Invalid piece character '{0}'")]
+    InvalidPiece(char),
+    #[error("Rank {0} does not describe exactly 8 squares")]
+    InvalidRank(u8),
+    #[error("Invalid side to move '{0}'")]
+    InvalidSideToMove(String),
+    #[error("Invalid castling rights '{0}'")]
+    InvalidCastlingRights(String),
+    #[error("Invalid en passant square '{0}'")]
+    InvalidEnPassant(String),
+    #[error("Invalid halfmove clock '{0}'")]
+    InvalidHalfmoveClock(String),
+    #[error("Invalid fullmove number '{0}'")]
+    InvalidFullmoveNumber(String),
+    #[error("Expected exactly one {0:?} king, found {1}")]
+    KingCount(Color, u32),
+    #[error("Pawn on back rank square {0}")]
+    PawnOnBackRank(String),
+    #[error("The side not to move is in check")]
+    OpponentInCheck,
+}
+
 /// A type alias for a bitboard
 pub type Bitboard = u64;
 
@@ -186,25 +607,60 @@ pub struct Clock {
     pub time_left: [TimeDelta; 2],
     pub current_turn_start: Timestamp,
     pub block_delay: TimeDelta,
+    pub time_control: TimeControl,
+    /// The time left of both players before each move, for takebacks
+    #[graphql(skip)]
+    pub history: Vec<[TimeDelta; 2]>,
 }
 
 impl Clock {
     /// Initializes the clock.
     pub fn new(block_time: Timestamp, arg: &InstantiationArgument) -> Self {
-        let total_time = TimeDelta::from_secs(900); // 15 mins
+        Self::with_time_control(block_time, arg.time_control, arg.block_delay)
+    }
+
+    /// Initializes the clock with a time control.
+    pub fn with_time_control(
+        block_time: Timestamp,
+        time_control: TimeControl,
+        block_delay: TimeDelta,
+    ) -> Self {
         Self {
-            time_left: [total_time, total_time],
-            // increment: arg.increment, // todo!(increment is not required at the moment)
+            time_left: [time_control.start_time; 2],
             current_turn_start: block_time,
-            block_delay: arg.block_delay,
+            block_delay,
+            time_control,
+            history: vec![],
         }
     }
 
+    /// Resets both players to the start time, with White's turn starting now.
+    pub fn restart(&mut self, block_time: Timestamp) {
+        self.time_left = [self.time_control.start_time; 2];
+        self.current_turn_start = block_time;
+        self.history.clear();
+    }
+
     /// Records a player making a move in the current block.
     pub fn make_move(&mut self, block_time: Timestamp, player: Color) {
+        self.history.push(self.time_left);
         let duration = block_time.delta_since(self.current_turn_start);
         let i = player.index();
-        self.time_left[i] = self.time_left[i].saturating_sub(duration);
+        let charged = self.time_control.charged_time(duration);
+        self.time_left[i] = self.time_left[i].saturating_sub(charged);
+        if self.time_control.mode == TimeMode::Fischer {
+            self.time_left[i] = self.time_left[i].saturating_add(self.time_control.increment);
+        }
+        self.current_turn_start = block_time;
+    }
+
+    /// Restores the time left before the last `plies` moves, the turn starting again now.
+    pub fn takeback(&mut self, plies: u32, block_time: Timestamp) {
+        for _ in 0..plies {
+            if let Some(time_left) = self.history.pop() {
+                self.time_left = time_left;
+            }
+        }
         self.current_turn_start = block_time;
     }
 
@@ -216,25 +672,30 @@ impl Clock {
         }
     }
 
-    /// Returns whether the given player has timed out.
+    /// Returns whether the given player has timed out. With a simple delay the clock only starts
+    /// after the delay, while Bronstein delay gives the time back only once the move is made.
     pub fn timed_out(&self, block_time: Timestamp, player: Color) -> bool {
-        self.time_left[player.index()] < block_time.delta_since(self.current_turn_start)
+        let mut allowance = self.time_left[player.index()];
+        if self.time_control.mode == TimeMode::SimpleDelay {
+            allowance = allowance.saturating_add(self.time_control.increment);
+        }
+        allowance < block_time.delta_since(self.current_turn_start)
     }
 }
 
-#[derive(Clone, Default, Debug, Serialize, Deserialize, SimpleObject)]
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
 pub struct Move {
     white: Option<String>,
     black: Option<String>,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CastleType {
     KingSide,
     QueenSide,
 }
 
-#[derive(Clone, Copy, Default, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MoveType {
     #[default]
     Move,
@@ -244,7 +705,7 @@ pub enum MoveType {
     Promotion(Piece),
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MoveData {
     pub from: Square,
     pub to: Square,
@@ -252,6 +713,28 @@ pub struct MoveData {
     pub move_type: MoveType, // Changed to `move_type` to avoid confusion with the `m` field
 }
 
+/// A type alias for a list of moves
+pub type MoveList = Vec<MoveData>;
+
+/// An undo record, holding everything a move overwrites so it can be unmade exactly
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Undo {
+    /// The move that was made
+    pub move_data: MoveData,
+    /// The piece captured by the move, if any
+    pub captured_piece: Option<Piece>,
+    /// Castling rights before the move
+    pub castling_rights: [bool; 4],
+    /// En passant square before the move
+    pub en_passant: Bitboard,
+    /// 50-Move Rule Counter before the move
+    pub halfmove_clock: u32,
+    /// Full move count before the move
+    pub fullmove_count: u32,
+    /// Zobrist hash before the move
+    pub hash: u64,
+}
+
 impl MoveData {
     pub fn new(from: Square, to: Square, piece: Piece, board: &ChessBoard) -> Self {
         let move_type = if let Some(captured_piece) = board.get_piece_at(to) {
@@ -267,14 +750,27 @@ impl MoveData {
             move_type,
         }
     }
+
+    /// A function to get the move in UCI notation (`e2e4`, `e7e8q`)
+    pub fn to_uci(&self) -> String {
+        let mut uci = format!(
+            "{}{}",
+            Square::usize_to_string(self.from as usize),
+            Square::usize_to_string(self.to as usize)
+        );
+        if let MoveType::Promotion(piece) = self.move_type {
+            uci.push(piece.letter().to_ascii_lowercase());
+        }
+        uci
+    }
 }
 
 /// The state of a Chess game.
-#[derive(Clone, Default, Serialize, Deserialize, SimpleObject)]
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize, SimpleObject)]
 pub struct Game {
     /// The current state of the board.
     pub board: ChessBoard,
-    /// The player whose turn it is. If the game has ended, this player loses.
+    /// The player whose turn it is.
     pub active: Color,
     /// Moves Table
     pub moves: Vec<Move>,
@@ -282,6 +778,8 @@ pub struct Game {
     pub captured_pieces: Vec<Piece>,
     /// Game State
     pub state: GameState,
+    /// The result of the game, once it has ended
+    pub result: Option<GameResult>,
     /// current zobrist hashing
     pub current_hash: u64,
     /// position_count
@@ -290,37 +788,224 @@ pub struct Game {
     pub halfmove_clock: u32,
     // represents full moves(increments when black makes a move)
     pub fullmove_count: u32,
+    /// Undo records of the moves made, most recent last
+    #[graphql(skip)]
+    pub history: Vec<Undo>,
 }
 
 impl Game {
     /// A function to create a new game using defaults
     pub fn new(&self) -> Self {
-        Game {
-            board: ChessBoard::new(),
-            active: Color::White,
-            moves: vec![],
-            captured_pieces: vec![],
-            state: GameState::InPlay,
-            current_hash: self.compute_zobrist_hash(),
-            position_count: HashMap::new(),
-            halfmove_clock: 0,
-            fullmove_count: 1,
-        }
+        Self::from_board(ChessBoard::new())
     }
 
-    /// A function to create a new game using FEN
+    /// A function to create a new game using FEN. Only the piece placement is required, missing
+    /// fields fall back to White to move and the counters of a new game. Use `Game::from_fen` to
+    /// validate a FEN.
     pub fn with_fen(&self, fen: &str) -> Self {
-        Game {
-            board: ChessBoard::with_fen(fen),
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let mut game = Self::from_board(ChessBoard::with_fen(fen));
+        if fields.get(1) == Some(&"b") {
+            game.active = Color::Black;
+        }
+        if let Some(halfmove_clock) = fields.get(4).and_then(|field| field.parse().ok()) {
+            game.halfmove_clock = halfmove_clock;
+        }
+        let fullmove_count = fields.get(5).and_then(|field| field.parse().ok());
+        if let Some(fullmove_count) = fullmove_count.filter(|&count| count > 0) {
+            game.fullmove_count = fullmove_count;
+        }
+        game.current_hash = game.compute_zobrist_hash();
+        game.position_count = HashMap::from([(game.current_hash, 1)]);
+        game
+    }
+
+    /// A function to create a game from all six FEN fields, validating that the position is
+    /// playable
+    pub fn from_fen(fen: &str) -> std::result::Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let [placement, side, castling, en_passant, halfmove, fullmove] = fields[..] else {
+            return Err(FenError::FieldCount(fields.len()));
+        };
+
+        let mut board = ChessBoard::from_placement(placement)?;
+
+        let active = match side {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::InvalidSideToMove(side.to_string())),
+        };
+
+        board.castling_rights = Self::parse_castling_rights(&board, castling)?;
+        board.en_passant = Self::parse_en_passant(&board, active, en_passant)?;
+
+        let halfmove_clock = halfmove
+            .parse::<u32>()
+            .map_err(|_| FenError::InvalidHalfmoveClock(halfmove.to_string()))?;
+        let fullmove_count = fullmove
+            .parse::<u32>()
+            .ok()
+            .filter(|&count| count > 0)
+            .ok_or_else(|| FenError::InvalidFullmoveNumber(fullmove.to_string()))?;
+
+        for (color, kings) in [(Color::White, board.wK), (Color::Black, board.bK)] {
+            if kings.count_ones() != 1 {
+                return Err(FenError::KingCount(color, kings.count_ones()));
+            }
+        }
+
+        let back_rank_pawns = (board.wP | board.bP) & 0xFF000000000000FF;
+        if back_rank_pawns != 0 {
+            let square = back_rank_pawns.trailing_zeros() as usize;
+            return Err(FenError::PawnOnBackRank(Square::usize_to_string(square)));
+        }
+
+        if board.in_check(active.opposite()) {
+            return Err(FenError::OpponentInCheck);
+        }
+
+        let mut game = Self::from_board(board);
+        game.active = active;
+        game.halfmove_clock = halfmove_clock;
+        game.fullmove_count = fullmove_count;
+        game.current_hash = game.compute_zobrist_hash();
+        game.position_count = HashMap::from([(game.current_hash, 1)]);
+        Ok(game)
+    }
+
+    /// A function to generate the FEN string of the game, it round-trips through `Game::from_fen`
+    pub fn to_fen(&self) -> String {
+        let side = match self.active {
+            Color::White => 'w',
+            Color::Black => 'b',
+        };
+
+        let castling: String = ['K', 'Q', 'k', 'q']
+            .iter()
+            .zip(self.board.castling_rights)
+            .filter_map(|(&c, right)| right.then_some(c))
+            .collect();
+        let castling = if castling.is_empty() {
+            "-".to_string()
+        } else {
+            castling
+        };
+
+        let en_passant = if self.board.en_passant == 0 {
+            "-".to_string()
+        } else {
+            Square::usize_to_string(self.board.en_passant.trailing_zeros() as usize)
+        };
+
+        format!(
+            "{} {} {} {} {} {}",
+            self.board.fen_placement(),
+            side,
+            castling,
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_count
+        )
+    }
+
+    /// Parses the FEN castling field, each right requires its king and rook on their home squares
+    fn parse_castling_rights(
+        board: &ChessBoard,
+        castling: &str,
+    ) -> std::result::Result<[bool; 4], FenError> {
+        let invalid = || FenError::InvalidCastlingRights(castling.to_string());
+        let mut rights = [false; 4];
+        if castling == "-" {
+            return Ok(rights);
+        }
+
+        for c in castling.chars() {
+            let (index, king, rook) = match c {
+                'K' => (
+                    0,
+                    board.wK & (1 << Square::E1 as u64),
+                    board.wR & (1 << Square::H1 as u64),
+                ),
+                'Q' => (
+                    1,
+                    board.wK & (1 << Square::E1 as u64),
+                    board.wR & (1 << Square::A1 as u64),
+                ),
+                'k' => (
+                    2,
+                    board.bK & (1 << Square::E8 as u64),
+                    board.bR & (1 << Square::H8 as u64),
+                ),
+                'q' => (
+                    3,
+                    board.bK & (1 << Square::E8 as u64),
+                    board.bR & (1 << Square::A8 as u64),
+                ),
+                _ => return Err(invalid()),
+            };
+            if rights[index] || king == 0 || rook == 0 {
+                return Err(invalid());
+            }
+            rights[index] = true;
+        }
+        Ok(rights)
+    }
+
+    /// Parses the FEN en passant field, the target must sit behind a pawn that could just have
+    /// made a double push
+    fn parse_en_passant(
+        board: &ChessBoard,
+        active: Color,
+        en_passant: &str,
+    ) -> std::result::Result<Bitboard, FenError> {
+        if en_passant == "-" {
+            return Ok(0);
+        }
+
+        let invalid = || FenError::InvalidEnPassant(en_passant.to_string());
+        let target = Square::from_str(en_passant).map_err(|_| invalid())?;
+        let rank = match active {
+            Color::White => 6,
+            Color::Black => 3,
+        };
+        if target.rank() != rank {
+            return Err(invalid());
+        }
+
+        // The pushed pawn stands one rank past the target and came from one rank before it
+        let (pawns, pushed, origin) = match active {
+            Color::White => (board.bP, target as u64 - 8, target as u64 + 8),
+            Color::Black => (board.wP, target as u64 + 8, target as u64 - 8),
+        };
+
+        let occupied = board.all_pieces();
+        let plausible =
+            pawns & (1 << pushed) != 0 && occupied & ((1 << target as u64) | (1 << origin)) == 0;
+        if !plausible {
+            return Err(invalid());
+        }
+        Ok(1 << target as u64)
+    }
+
+    /// A function to create a game with White to move from a board, counting the starting
+    /// position for repetitions
+    fn from_board(board: ChessBoard) -> Self {
+        let mut game = Game {
+            board,
             active: Color::White,
             moves: vec![],
             captured_pieces: vec![],
             state: GameState::InPlay,
-            current_hash: self.compute_zobrist_hash(),
+            result: None,
+            current_hash: 0,
             position_count: HashMap::new(),
             halfmove_clock: 0,
             fullmove_count: 1,
-        }
+            history: vec![],
+        };
+        game.current_hash = game.compute_zobrist_hash();
+        game.position_count.insert(game.current_hash, 1);
+        game
     }
 
     /// A function to compute zobrist hashing
@@ -355,11 +1040,18 @@ impl Game {
         hash
     }
 
-    /// Check for threefold_repetition
-    pub fn check_threefold_repetition(&mut self) -> bool {
-        let count = self.position_count.entry(self.current_hash).or_insert(0);
-        *count += 1;
-        *count == 3
+    /// Check for threefold_repetition, positions are counted as moves are made
+    pub fn check_threefold_repetition(&self) -> bool {
+        self.position_count
+            .get(&self.current_hash)
+            .is_some_and(|&count| count >= 3)
+    }
+
+    /// Check for fivefold repetition, which ends the game without a claim
+    pub fn check_fivefold_repetition(&self) -> bool {
+        self.position_count
+            .get(&self.current_hash)
+            .is_some_and(|&count| count >= 5)
     }
 
     /// A function to insert the captured_pieces into a vec
@@ -400,6 +1092,69 @@ impl Game {
         }
     }
 
+    /// A function to get the Standard Algebraic Notation of a legal move in the current position,
+    /// with piece letter, disambiguation, capture, promotion and check or mate suffix
+    pub fn to_san(&self, mv: &MoveData) -> String {
+        let mut san = match mv.move_type {
+            MoveType::Castle(CastleType::KingSide) => "O-O".to_string(),
+            MoveType::Castle(CastleType::QueenSide) => "O-O-O".to_string(),
+            _ => {
+                let is_capture =
+                    mv.move_type == MoveType::EnPassant || self.board.get_piece_at(mv.to).is_some();
+                let mut san = String::new();
+
+                if mv.piece.is_pawn() {
+                    if is_capture {
+                        san.push(mv.from.file());
+                    }
+                } else {
+                    san.push(mv.piece.letter());
+                    // other pieces of the same kind that can reach the same square
+                    let rivals: Vec<Square> = self
+                        .board
+                        .legal_moves(self.active)
+                        .iter()
+                        .filter(|other| {
+                            other.piece == mv.piece && other.to == mv.to && other.from != mv.from
+                        })
+                        .map(|other| other.from)
+                        .collect();
+                    if !rivals.is_empty() {
+                        if rivals.iter().all(|sq| sq.file() != mv.from.file()) {
+                            san.push(mv.from.file());
+                        } else if rivals.iter().all(|sq| sq.rank() != mv.from.rank()) {
+                            san.push_str(&mv.from.rank().to_string());
+                        } else {
+                            san.push_str(&Square::usize_to_string(mv.from as usize));
+                        }
+                    }
+                }
+
+                if is_capture {
+                    san.push('x');
+                }
+                san.push_str(&Square::usize_to_string(mv.to as usize));
+                if let MoveType::Promotion(piece) = mv.move_type {
+                    san.push('=');
+                    san.push(piece.letter());
+                }
+                san
+            }
+        };
+
+        let mut board = self.board;
+        board.apply_move(mv);
+        let opponent = self.active.opposite();
+        if board.in_check(opponent) {
+            san.push(if board.legal_moves(opponent).is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        san
+    }
+
     /// A function to get active player
     pub fn active_player(&self) -> Color {
         self.active
@@ -407,13 +1162,13 @@ impl Game {
 
     /// A function to switch player turn
     pub fn switch_player_turn(&mut self) {
-        self.active = self.active.opposite();
-
         if self.active == Color::Black {
             self.fullmove_count += 1
         }
 
-        update_side_hash(self.active, &mut self.current_hash);
+        self.active = self.active.opposite();
+
+        update_side_hash(&mut self.current_hash);
     }
 
     /// A function to reset the halfmove_clock to 0,on pawn move or a piece capture
@@ -426,263 +1181,829 @@ impl Game {
         self.halfmove_clock += 1
     }
 
-    /// Check if halfmove_clock is greater or equals to 100, [Draw]
+    /// Check if halfmove_clock is greater or equals to 100, a draw can be claimed
     pub fn check_50_move_rule(&self) -> bool {
         self.halfmove_clock >= 100
     }
 
-    /// A function to make move
-    pub fn make_move(&mut self, from: Square, to: Square, piece: Piece, m: MoveType) -> Result<()> {
-        let color = piece.color().opposite();
-        match m {
-            MoveType::Move => match self.move_piece(from, to, piece) {
-                Ok(_) => {
-                    // Revoke castling rights for a ColorSide permanently
-                    if piece == Piece::WhiteRook || piece == Piece::BlackRook {
-                        self.board.revoke_castling_rights(color, from);
-                        update_castle_hash(self.board.castling_rights, &mut self.current_hash);
-                    }
+    /// Check if halfmove_clock is greater or equals to 150, which ends the game without a claim
+    pub fn check_75_move_rule(&self) -> bool {
+        self.halfmove_clock >= 150
+    }
 
-                    // update halfmove_clock based on piece(halfmove_clock's reset is done whenever pawn moves or a piece is captured)
-                    if piece == Piece::WhitePawn || piece == Piece::BlackPawn {
-                        self.reset_halfmove_clock();
-                    } else {
-                        self.update_halfmove_clock();
-                    }
+    /// A function to make a move for the active player. The move must be in the legal move list,
+    /// it is played on the board, the hash, clocks and repetition count are updated, the turn
+    /// passes, and the returned undo record is also pushed on `history`
+    pub fn make_move(
+        &mut self,
+        from: Square,
+        to: Square,
+        piece: Piece,
+        m: MoveType,
+    ) -> Result<Undo> {
+        let move_data = MoveData {
+            from,
+            to,
+            piece,
+            move_type: m,
+        };
 
-                    update_piece_hash(from, piece, &mut self.current_hash);
-                    update_piece_hash(to, piece, &mut self.current_hash);
+        if piece.color() != self.active || !self.board.legal_moves(self.active).contains(&move_data)
+        {
+            return Err(match m {
+                MoveType::Move => ChessError::InvalidMove,
+                MoveType::Capture(_) => ChessError::InvalidCapture,
+                MoveType::Castle(_) => ChessError::InvalidCastle,
+                MoveType::EnPassant => ChessError::InvalidEnPassant,
+                MoveType::Promotion(_) => ChessError::InvalidPromotion,
+            });
+        }
 
-                    Ok(())
-                }
-                Err(e) => Err(e),
-            },
-            MoveType::Capture(Piece) => match self.capture_piece(from, to, piece, Piece) {
-                Ok(_) => {
-                    // Revoke castling rights for a ColorSide permanently(K,Q,k,q), when a rook is caputred at the starting position
-                    if Piece == Piece::WhiteRook || Piece == Piece::BlackRook {
-                        self.board.revoke_castling_rights(color, to);
-                        update_castle_hash(self.board.castling_rights, &mut self.current_hash);
-                    }
+        Ok(self.make_move_unchecked(move_data))
+    }
 
-                    update_piece_hash(from, piece, &mut self.current_hash); // XOR out from the starting square
-                    update_piece_hash(to, Piece, &mut self.current_hash); // XOR out captured piece
-                    update_piece_hash(to, piece, &mut self.current_hash); // XOR in moving piece to new square
+    /// A function to play a move taken from `legal_moves` for the active player without
+    /// validating it again, for search and perft which already generated the move list
+    pub fn make_move_unchecked(&mut self, move_data: MoveData) -> Undo {
+        let mut undo = Undo {
+            move_data,
+            captured_piece: None,
+            castling_rights: self.board.castling_rights,
+            en_passant: self.board.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_count: self.fullmove_count,
+            hash: self.current_hash,
+        };
 
-                    self.insert_captured_pieces(&Piece);
-                    self.reset_halfmove_clock();
-                    Ok(())
-                }
-                Err(e) => Err(e),
-            },
-            MoveType::Castle(CastleType::KingSide) => {
-                match self.castle(&piece, CastleType::KingSide) {
-                    Ok(_) => {
-                        self.board.update_castling_rights(color); // revoke castling rights after castling
-                        update_castle_hash(self.board.castling_rights, &mut self.current_hash);
-                        self.update_halfmove_clock();
-                        Ok(())
-                    }
-                    Err(e) => Err(e),
-                }
-            }
-            MoveType::Castle(CastleType::QueenSide) => {
-                match self.castle(&piece, CastleType::QueenSide) {
-                    Ok(_) => {
-                        self.board.update_castling_rights(color); // revoke castling rights after castling
-                        update_castle_hash(self.board.castling_rights, &mut self.current_hash);
-                        self.update_halfmove_clock();
-                        Ok(())
-                    }
-                    Err(e) => Err(e),
-                }
-            }
-            MoveType::EnPassant => match self.board.en_passant_capture(from, to, &piece) {
-                Ok(_) => {
-                    self.insert_captured_pieces(&piece.opp_piece()); // In case of en passant, only pawns can be captured
-                    update_ep_hash(to, &mut self.current_hash);
-                    self.reset_halfmove_clock();
-                    Ok(())
-                }
+        undo.captured_piece = self.board.apply_move(&move_data);
+        self.update_move_hash(&undo);
 
-                Err(e) => Err(e),
-            },
-            MoveType::Promotion(Piece) => {
-                update_piece_hash(from, piece, &mut self.current_hash);
+        // halfmove_clock's reset is done whenever pawn moves or a piece is captured
+        if let Some(captured_piece) = undo.captured_piece {
+            self.insert_captured_pieces(&captured_piece);
+            self.reset_halfmove_clock();
+        } else if move_data.piece.is_pawn() {
+            self.reset_halfmove_clock();
+        } else {
+            self.update_halfmove_clock();
+        }
 
-                if let Some(captured_piece) = self.board.get_piece_at(to) {
-                    log::info!("Promotion and Capture: {:?}", captured_piece);
-                    update_piece_hash(to, captured_piece, &mut self.current_hash); // Remove captured piece
-                    self.capture_piece(from, to, piece, captured_piece)?;
-                } else {
-                    log::info!("Promotion without Capture");
-                    self.move_piece(from, to, piece)?; // Move without capture
-                }
+        self.switch_player_turn();
+        *self.position_count.entry(self.current_hash).or_insert(0) += 1;
+        self.history.push(undo);
+
+        undo
+    }
 
-                update_piece_hash(to, Piece, &mut self.current_hash);
-                self.update_halfmove_clock();
+    /// A function to unmake the last move, restoring the exact previous state
+    pub fn unmake_move(&mut self) -> Result<Undo> {
+        let undo = self.history.pop().ok_or(ChessError::InvalidRequest)?;
 
-                self.board.add_piece(to, piece, Piece)
+        if let Some(count) = self.position_count.get_mut(&self.current_hash) {
+            *count -= 1;
+            if *count == 0 {
+                self.position_count.remove(&self.current_hash);
             }
         }
+
+        self.board.undo_move(&undo)?;
+        if undo.captured_piece.is_some() {
+            self.captured_pieces.pop();
+        }
+
+        self.active = self.active.opposite();
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_count = undo.fullmove_count;
+        self.current_hash = undo.hash;
+
+        Ok(undo)
     }
 
-    /// A function to castle
-    pub fn castle(&mut self, piece: &Piece, castle_type: CastleType) -> Result<()> {
-        match piece {
-            Piece::WhiteKing => {
-                if !self.board.castling_rights[0] {
-                    return Err(ChessError::CastleRights);
+    /// A function to take back the last `plies` moves together with their move table entries
+    pub fn takeback(&mut self, plies: u32) -> Result<()> {
+        if plies == 0 || plies as usize > self.history.len() {
+            return Err(ChessError::InvalidRequest);
+        }
+
+        for _ in 0..plies {
+            self.unmake_move()?;
+            // the side to move again is the one whose move was taken back
+            if let Some(last) = self.moves.last_mut() {
+                match self.active {
+                    Color::White => last.white = None,
+                    Color::Black => last.black = None,
                 }
-                match castle_type {
-                    CastleType::KingSide => self.board.wK_castle_king_side(),
-                    CastleType::QueenSide => self.board.wK_castle_queen_side(),
+                if last.white.is_none() && last.black.is_none() {
+                    self.moves.pop();
                 }
             }
-            Piece::BlackKing => {
-                if !self.board.castling_rights[1] {
-                    return Err(ChessError::CastleRights);
-                }
-                match castle_type {
-                    CastleType::KingSide => self.board.bK_castle_king_side(),
-                    CastleType::QueenSide => self.board.bK_castle_queen_side(),
-                }
+        }
+        Ok(())
+    }
+
+    /// A function to update the zobrist hash for the board change of a move, the side to move
+    /// is hashed by `switch_player_turn`
+    fn update_move_hash(&mut self, undo: &Undo) {
+        let mv = &undo.move_data;
+        let hash = &mut self.current_hash;
+
+        // XOR out the previous en passant square and castling rights, XOR in the new ones
+        if undo.en_passant != 0 {
+            update_ep_hash(
+                Square::usize_to_square(undo.en_passant.trailing_zeros() as usize),
+                hash,
+            );
+        }
+        if self.board.en_passant != 0 {
+            update_ep_hash(
+                Square::usize_to_square(self.board.en_passant.trailing_zeros() as usize),
+                hash,
+            );
+        }
+        update_castle_hash(undo.castling_rights, hash);
+        update_castle_hash(self.board.castling_rights, hash);
+
+        let placed = match mv.move_type {
+            MoveType::Promotion(promoted) => promoted,
+            _ => mv.piece,
+        };
+        update_piece_hash(mv.from, mv.piece, hash);
+        update_piece_hash(mv.to, placed, hash);
+
+        if let Some(captured_piece) = undo.captured_piece {
+            update_piece_hash(ChessBoard::captured_square(mv), captured_piece, hash);
+        }
+
+        if let MoveType::Castle(castle_type) = mv.move_type {
+            let color = mv.piece.color();
+            let rook = Piece::from_letter('R', color).expect("Invalid piece");
+            let (rook_from, rook_to) = ChessBoard::castle_rook_squares(color, castle_type);
+            update_piece_hash(rook_from, rook, hash);
+            update_piece_hash(rook_to, rook, hash);
+        }
+    }
+
+    /// A function to parse a move for the active player in SAN (`Nf3`, `exd8=Q+`, `O-O`) or long
+    /// algebraic notation (`e2e4`, `e7e8q`, `Ng1-f3`, `e5xd6`), deriving the piece and the move
+    /// type from the board. Check and capture marks and the `=` of promotions are optional
+    pub fn parse_move(&self, notation: &str) -> Result<MoveData> {
+        let notation = notation.trim().trim_end_matches(['+', '#', '!', '?']);
+        let bytes = notation.as_bytes();
+
+        // Long algebraic notation: optional piece letter, origin, optional `-`/`x`, target and
+        // optional promotion
+        let (letter, rest) = match bytes.first() {
+            Some(b'K' | b'Q' | b'R' | b'B' | b'N' | b'P') => (Some(bytes[0] as char), &bytes[1..]),
+            _ => (None, bytes),
+        };
+        let rest = match rest {
+            [f1, r1, b'-' | b'x', rest @ ..] | [f1 @ b'a'..=b'h', r1 @ b'1'..=b'8', rest @ ..] => {
+                Some((*f1, *r1, rest))
+            }
+            _ => None,
+        };
+        if let Some((f1, r1, [f2 @ b'a'..=b'h', r2 @ b'1'..=b'8', promotion @ ..])) = rest {
+            let square = |file: u8, rank: u8| {
+                Square::from_str(&format!("{}{}", file as char, rank as char))
+                    .map_err(|_| ChessError::InvalidNotation)
+            };
+            let from = square(f1, r1)?;
+            let to = square(*f2, *r2)?;
+            let promotion = self.parse_promotion(promotion)?;
+
+            let move_data = self.classify_move(from, to, promotion)?;
+            if letter.is_some_and(|letter| letter != move_data.piece.letter()) {
+                return Err(ChessError::InvalidMove);
             }
-            _ => Err(ChessError::InvalidPiece),
+            return Ok(move_data);
         }
+
+        self.san_to_move(notation)
     }
 
-    /// A function to move piece
-    pub fn move_piece(&mut self, from: Square, to: Square, piece: Piece) -> Result<()> {
-        match piece {
-            Piece::WhitePawn => self.board.wP_moves(from, to, &piece),
-            Piece::BlackPawn => self.board.bP_moves(from, to, &piece),
-            Piece::WhiteKnight | Piece::BlackKnight => self.board.knight_moves(from, to, &piece),
-            Piece::WhiteKing | Piece::BlackKing => self.board.king_moves(from, to, &piece),
-            Piece::WhiteBishop | Piece::BlackBishop => self.board.bishop_moves(from, to, &piece),
-            Piece::WhiteRook | Piece::BlackRook => self.board.rook_moves(from, to, &piece),
-            Piece::WhiteQueen | Piece::BlackQueen => self.board.queen_moves(from, to, &piece),
+    /// Reads an optional promotion suffix (`=Q`, `Q` or `q`)
+    fn parse_promotion(&self, suffix: &[u8]) -> Result<Option<Piece>> {
+        match suffix {
+            [] => Ok(None),
+            [b'=', letter] | [letter] => Piece::from_letter(*letter as char, self.active)
+                .map(Some)
+                .ok_or(ChessError::InvalidNotation),
+            _ => Err(ChessError::InvalidNotation),
         }
     }
 
-    /// a function to capture piece
-    pub fn capture_piece(
-        &mut self,
+    /// A function to derive the piece and move type of a move from the board
+    pub fn classify_move(
+        &self,
         from: Square,
         to: Square,
-        piece: Piece,
-        captured_piece: Piece,
-    ) -> Result<()> {
-        if piece.color() == captured_piece.color() {
-            return Err(ChessError::InvalidCapture);
+        promotion: Option<Piece>,
+    ) -> Result<MoveData> {
+        let piece = self
+            .board
+            .get_piece_at(from)
+            .ok_or(ChessError::InvalidMove)?;
+
+        if piece.color() != self.active {
+            return Err(ChessError::InvalidMove);
         }
 
-        if self.board.get_piece_at(to).is_none() {
-            return Err(ChessError::InvalidCapture);
-        }
-        match piece {
-            Piece::WhitePawn => self.board.wP_captures(from, to, &piece, &captured_piece),
-            Piece::BlackPawn => self.board.bP_captures(from, to, &piece, &captured_piece),
-            Piece::WhiteKnight | Piece::BlackKnight => {
-                self.board
-                    .knight_captures(from, to, &piece, &captured_piece)
-            }
-            Piece::WhiteKing | Piece::BlackKing => {
-                self.board.king_captures(from, to, &piece, &captured_piece)
-            }
-            Piece::WhiteRook | Piece::BlackRook => {
-                self.board.rook_captures(from, to, &piece, &captured_piece)
-            }
-            Piece::WhiteBishop | Piece::BlackBishop => {
-                self.board
-                    .bishop_captures(from, to, &piece, &captured_piece)
+        let last_rank = match self.active {
+            Color::White => 8,
+            Color::Black => 1,
+        };
+
+        let move_type = if piece.is_king() && from.file() == 'e' && from.rank() == to.rank() {
+            match to.file() {
+                'g' => MoveType::Castle(CastleType::KingSide),
+                'c' => MoveType::Castle(CastleType::QueenSide),
+                _ => self.capture_or_move(to),
             }
-            Piece::WhiteQueen | Piece::BlackQueen => {
-                self.board.queen_captures(from, to, &piece, &captured_piece)
+        } else if piece.is_pawn() && to.rank() == last_rank {
+            match promotion {
+                Some(promoted) if !promoted.is_pawn() && !promoted.is_king() => {
+                    MoveType::Promotion(promoted)
+                }
+                _ => return Err(ChessError::InvalidPromotion),
             }
+        } else if piece.is_pawn()
+            && from.file() != to.file()
+            && self.board.en_passant & (1u64 << to as usize) != 0
+        {
+            MoveType::EnPassant
+        } else {
+            self.capture_or_move(to)
+        };
+
+        if promotion.is_some() && !matches!(move_type, MoveType::Promotion(_)) {
+            return Err(ChessError::InvalidPromotion);
+        }
+
+        Ok(MoveData {
+            from,
+            to,
+            piece,
+            move_type,
+        })
+    }
+
+    /// Returns a capture if the target square is occupied, otherwise a plain move
+    fn capture_or_move(&self, to: Square) -> MoveType {
+        match self.board.get_piece_at(to) {
+            Some(captured_piece) => MoveType::Capture(captured_piece),
+            None => MoveType::Move,
         }
     }
 
-    /// A function to check stalemate, returns true if stalemate(this checks for a possible legal
-    /// move)
-    pub fn is_stalemate(&mut self, total_pieces: Bitboard) -> bool {
-        let mut pieces = total_pieces;
-        while pieces != 0 {
-            let from: usize = pieces.trailing_zeros() as usize;
-            let square = Square::usize_to_square(from);
-            if let Some(piece) = self.board.get_piece_at(square) {
-                if let Some(_possible_moves) = self.get_possible_moves(square, piece) {
-                    return false;
+    /// A function to resolve a SAN move by finding the only piece that can legally play it
+    fn san_to_move(&self, san: &str) -> Result<MoveData> {
+        let (home, last_rank) = match self.active {
+            Color::White => ("1", "8"),
+            Color::Black => ("8", "1"),
+        };
+
+        let castle_to = match san {
+            "O-O" | "0-0" => Some("g"),
+            "O-O-O" | "0-0-0" => Some("c"),
+            _ => None,
+        };
+        if let Some(file) = castle_to {
+            let from = Square::from_str(&format!("e{home}")).expect("Invalid square");
+            let to = Square::from_str(&format!("{file}{home}")).expect("Invalid square");
+            return self.classify_move(from, to, None);
+        }
+
+        // Split off the promotion suffix (`=Q`, or `Q` right after the target rank)
+        let split = match san.find('=') {
+            Some(index) => index,
+            None => match san.as_bytes() {
+                [.., b'1'..=b'8', b'Q' | b'R' | b'B' | b'N' | b'q' | b'r' | b'b' | b'n'] => {
+                    san.len() - 1
                 }
+                _ => san.len(),
+            },
+        };
+        let (body, promotion) = san.split_at(split);
+        let promotion = self.parse_promotion(promotion.as_bytes())?;
+
+        // Piece letter, if any; pawns have none
+        let (letter, body) = match body.chars().next() {
+            Some(c @ ('K' | 'Q' | 'R' | 'B' | 'N')) => (c, &body[1..]),
+            Some(_) => ('P', body),
+            None => return Err(ChessError::InvalidNotation),
+        };
+        let piece = Piece::from_letter(letter, self.active).ok_or(ChessError::InvalidNotation)?;
+
+        let body: String = body.chars().filter(|&c| c != 'x').collect();
+        if body.len() < 2 || !body.is_ascii() {
+            return Err(ChessError::InvalidNotation);
+        }
+        let (hint, target) = body.split_at(body.len() - 2);
+        let to = Square::from_str(target).map_err(|_| ChessError::InvalidNotation)?;
+
+        if piece.is_pawn() && target.ends_with(last_rank) != promotion.is_some() {
+            return Err(ChessError::InvalidPromotion);
+        }
+
+        // Disambiguation: an origin file, rank or both
+        let mut hint_file = None;
+        let mut hint_rank = None;
+        for c in hint.chars() {
+            match c {
+                'a'..='h' if hint_file.is_none() => hint_file = Some(c),
+                '1'..='8' if hint_rank.is_none() => hint_rank = c.to_digit(10).map(|r| r as u8),
+                _ => return Err(ChessError::InvalidNotation),
             }
+        }
 
-            pieces &= pieces - 1;
+        let candidates: MoveList = self
+            .board
+            .legal_moves(self.active)
+            .into_iter()
+            .filter(|mv| {
+                mv.piece == piece
+                    && mv.to == to
+                    && hint_file.map_or(true, |f| f == mv.from.file())
+                    && hint_rank.map_or(true, |r| r == mv.from.rank())
+                    // SAN pawn pushes never change file, pawn captures always name it
+                    && (!piece.is_pawn() || hint_file.is_some() || mv.from.file() == to.file())
+                    && match mv.move_type {
+                        MoveType::Promotion(promoted) => Some(promoted) == promotion,
+                        MoveType::Castle(_) => false,
+                        _ => promotion.is_none(),
+                    }
+            })
+            .collect();
+
+        match candidates.as_slice() {
+            [move_data] => Ok(*move_data),
+            [] => Err(ChessError::InvalidMove),
+            _ => Err(ChessError::AmbiguousMove),
         }
-        true
     }
 
-    /// Check if the current player is in checkmate
-    pub fn is_checkmate(&mut self) -> bool {
-        let color = self.active;
-
-        let mut pieces = match color {
-            Color::White => self.board.white_pieces(),
-            Color::Black => self.board.black_pieces(),
+    /// A function to end the game with the given result, updating the game state to match
+    pub fn finish(&mut self, result: GameResult) {
+        self.state = match result.reason {
+            Termination::Checkmate => GameState::Checkmate,
+            Termination::Resignation => GameState::Resign,
+            Termination::Timeout => GameState::Timeout,
+            Termination::Stalemate => GameState::Stalemate,
+            Termination::Agreement | Termination::Repetition | Termination::FiftyMove => {
+                GameState::Draw
+            }
+            Termination::InsufficientMaterial => GameState::InsufficientMaterial,
+            Termination::Abandoned => GameState::Abandoned,
         };
+        self.result = Some(result);
+    }
 
-        // if not in check return false
-        if !self.board.in_check(color) && self.is_stalemate(pieces) {
-            self.state = GameState::Stalemate;
+    /// A function to check stalemate, returns true if the active player is not in check and has
+    /// no legal move
+    pub fn is_stalemate(&self) -> bool {
+        !self.board.in_check(self.active) && self.board.legal_moves(self.active).is_empty()
+    }
+
+    /// Check if the current player is in checkmate
+    pub fn is_checkmate(&self) -> bool {
+        self.board.in_check(self.active) && self.board.legal_moves(self.active).is_empty()
+    }
+
+    /// Check if neither player can checkmate, i.e K vs K, K+minor vs K or only bishops on squares
+    /// of the same color
+    pub fn is_insufficient_material(&self) -> bool {
+        let board = &self.board;
+        if board.wP | board.bP | board.wR | board.bR | board.wQ | board.bQ != 0 {
             return false;
         }
 
-        // Try all possible moves for all pieces of the current player
-        while pieces != 0 {
-            let from: usize = pieces.trailing_zeros() as usize;
-            let square = Square::usize_to_square(from);
-            if let Some(piece) = self.board.get_piece_at(square) {
-                if let Some(possible_moves) = self.get_possible_moves(square, piece) {
-                    for mv in possible_moves {
-                        // Create a copy of the board to test moves
-                        let mut temp_board = self.clone();
-                        match temp_board.make_move(mv.from, mv.to, mv.piece, mv.move_type) {
-                            Ok(_) => {
-                                if !temp_board.board.in_check(color) {
-                                    return false;
-                                }
-                            }
-                            Err(e) => {
-                                log::trace!("Error: {:?}", e);
-                            }
-                        }
-                    }
+        let knights = board.wN | board.bN;
+        let bishops = board.wB | board.bB;
+        if (knights | bishops).count_ones() <= 1 {
+            return true;
+        }
+
+        knights == 0 && (bishops & LIGHT_SQUARES == 0 || bishops & !LIGHT_SQUARES == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_ratings() {
+        let mut stats = PlayerStats::new("player".to_string());
+        assert_eq!(stats.rating(), INITIAL_RATING);
+        stats.record_win();
+        assert_eq!(stats.rating(), 1900);
+        stats.record_loss();
+        stats.record_draw();
+        assert_eq!(stats.rating(), 1500);
+        for _ in 0..3 {
+            stats.record_loss();
+        }
+        assert_eq!(stats.rating(), 1300);
+        assert!(RatingRange {
+            min: 1200,
+            max: 1300
+        }
+        .contains(stats.rating()));
+    }
+
+    #[test]
+    fn unmake_move_restores_the_exact_state() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+
+        for fen in fens {
+            let mut game = Game::default().with_fen(fen);
+            let start = game.clone();
+
+            for mv in game.board.legal_moves(game.active) {
+                game.make_move(mv.from, mv.to, mv.piece, mv.move_type)
+                    .expect("Legal move should be playable");
+                assert_eq!(game.current_hash, game.compute_zobrist_hash(), "{mv:?}");
+                let after_move = game.clone();
+
+                for reply in game.board.legal_moves(game.active) {
+                    game.make_move(reply.from, reply.to, reply.piece, reply.move_type)
+                        .expect("Legal move should be playable");
+                    assert_eq!(game.current_hash, game.compute_zobrist_hash(), "{reply:?}");
+                    game.unmake_move().expect("Undo failed");
+                    assert_eq!(game, after_move, "{mv:?} {reply:?}");
                 }
+
+                game.unmake_move().expect("Undo failed");
+                assert_eq!(game, start, "{mv:?}");
             }
+        }
+    }
+
+    #[test]
+    fn illegal_moves_leave_the_game_untouched() {
+        let mut game = Game::default().new();
+        let start = game.clone();
+
+        assert_eq!(
+            game.make_move(Square::E2, Square::E5, Piece::WhitePawn, MoveType::Move),
+            Err(ChessError::InvalidMove)
+        );
+        assert_eq!(
+            game.make_move(Square::E7, Square::E5, Piece::BlackPawn, MoveType::Move),
+            Err(ChessError::InvalidMove)
+        );
+        assert_eq!(game.unmake_move(), Err(ChessError::InvalidRequest));
+        assert_eq!(game, start);
+    }
+
+    #[test]
+    fn repeated_positions_are_counted() {
+        let mut game = Game::default().new();
+        let shuffle = [
+            (Square::G1, Square::F3, Piece::WhiteKnight),
+            (Square::G8, Square::F6, Piece::BlackKnight),
+            (Square::F3, Square::G1, Piece::WhiteKnight),
+            (Square::F6, Square::G8, Piece::BlackKnight),
+        ];
+
+        for _ in 0..2 {
+            assert!(!game.check_threefold_repetition());
+            for (from, to, piece) in shuffle {
+                game.make_move(from, to, piece, MoveType::Move).unwrap();
+            }
+        }
+        assert!(game.check_threefold_repetition());
+        assert_eq!(game.fullmove_count, 5);
+        assert_eq!(game.halfmove_clock, 8);
+
+        for _ in 0..2 {
+            assert!(!game.check_fivefold_repetition());
+            for (from, to, piece) in shuffle {
+                game.make_move(from, to, piece, MoveType::Move).unwrap();
+            }
+        }
+        assert!(game.check_fivefold_repetition());
+    }
+
+    #[test]
+    fn from_fen_parses_all_fields() {
+        let game =
+            Game::from_fen("rnbqkbnr/pppp1ppp/8/8/3pP3/8/PPP2PPP/RNBQKBNR b Kq e3 4 12").unwrap();
+
+        assert_eq!(game.active, Color::Black);
+        assert_eq!(game.board.castling_rights, [true, false, false, true]);
+        assert_eq!(game.board.en_passant, 1 << Square::E3 as u64);
+        assert_eq!(game.halfmove_clock, 4);
+        assert_eq!(game.fullmove_count, 12);
+        assert_eq!(game.current_hash, game.compute_zobrist_hash());
+        assert_eq!(game.board.legal_moves(Color::Black).len(), 31);
+    }
 
-            pieces &= pieces - 1;
+    #[test]
+    fn from_fen_rejects_invalid_positions() {
+        let cases = [
+            ("8/8/8/8/8/8/8/K6k w - -", FenError::FieldCount(4)),
+            ("8/8/8/8/8/8/K6k w - - 0 1", FenError::RankCount(7)),
+            ("8/8/8/8/8/8/8/K5xk w - - 0 1", FenError::InvalidPiece('x')),
+            ("8/8/8/8/8/8/8/K7k w - - 0 1", FenError::InvalidRank(1)),
+            (
+                "8/8/8/8/8/8/8/K6k x - - 0 1",
+                FenError::InvalidSideToMove("x".into()),
+            ),
+            (
+                "8/8/8/8/8/8/8/K6k w K - 0 1",
+                FenError::InvalidCastlingRights("K".into()),
+            ),
+            (
+                "r3k3/8/8/8/8/8/8/4K3 w qq - 0 1",
+                FenError::InvalidCastlingRights("qq".into()),
+            ),
+            (
+                "8/8/8/8/8/8/8/K6k w - e3 0 1",
+                FenError::InvalidEnPassant("e3".into()),
+            ),
+            (
+                "8/8/8/3pP3/8/8/8/K6k w - e6 0 1",
+                FenError::InvalidEnPassant("e6".into()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - a1 0 1",
+                FenError::InvalidEnPassant("a1".into()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 b - h8 0 1",
+                FenError::InvalidEnPassant("h8".into()),
+            ),
+            (
+                "8/8/8/8/8/8/8/K6k w - - x 1",
+                FenError::InvalidHalfmoveClock("x".into()),
+            ),
+            (
+                "8/8/8/8/8/8/8/K6k w - - 0 0",
+                FenError::InvalidFullmoveNumber("0".into()),
+            ),
+            (
+                "8/8/8/8/8/8/8/K5KK w - - 0 1",
+                FenError::KingCount(Color::White, 3),
+            ),
+            (
+                "8/8/8/8/8/8/8/K7 w - - 0 1",
+                FenError::KingCount(Color::Black, 0),
+            ),
+            (
+                "P7/8/8/8/8/8/8/K6k w - - 0 1",
+                FenError::PawnOnBackRank("a8".into()),
+            ),
+            ("8/8/8/8/8/8/8/K5Rk w - - 0 1", FenError::OpponentInCheck),
+        ];
+
+        for (fen, error) in cases {
+            assert_eq!(Game::from_fen(fen), Err(error), "{fen}");
         }
-        true
     }
 
-    /// Get all possible moves for a piece
-    fn get_possible_moves(&self, from: Square, piece: Piece) -> Option<Vec<MoveData>> {
-        let color = piece.color();
-        let moves = match piece {
-            Piece::WhitePawn | Piece::BlackPawn => self.board.get_pawn_moves(from, color),
-            Piece::WhiteKnight | Piece::BlackKnight => self.board.get_knight_moves(from, color),
-            Piece::WhiteKing | Piece::BlackKing => self.board.get_king_moves(from, color),
-            Piece::WhiteBishop | Piece::BlackBishop => self.board.get_bishop_moves(from, color),
-            Piece::WhiteRook | Piece::BlackRook => self.board.get_rook_moves(from, color),
-            Piece::WhiteQueen | Piece::BlackQueen => self.board.get_queen_moves(from, color),
+    #[test]
+    fn with_fen_fills_in_missing_fields() {
+        let start = Game::default().new();
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+        ] {
+            let game = Game::default().with_fen(fen);
+            let castling_rights = start.board.castling_rights;
+            assert_eq!(
+                ChessBoard {
+                    castling_rights,
+                    ..game.board
+                },
+                start.board
+            );
+            assert_eq!(game.active, Color::White);
+            assert_eq!((game.halfmove_clock, game.fullmove_count), (0, 1));
+        }
+
+        let game = Game::default().with_fen("4k3/8/8/8/8/8/8/4K3 b - - 7 30");
+        assert_eq!(game.active, Color::Black);
+        assert_eq!((game.halfmove_clock, game.fullmove_count), (7, 30));
+    }
+
+    #[test]
+    fn to_fen_round_trips() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppp1ppp/8/8/3pP3/8/PPP2PPP/RNBQKBNR b Kq e3 4 12",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "4k3/8/8/8/8/8/8/R3K1rR w KQ - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ];
+
+        for fen in fens {
+            assert_eq!(Game::from_fen(fen).unwrap().to_fen(), fen);
+        }
+
+        let mut game = Game::default().new();
+        for chess_move in ["e2e4", "c7c5", "e4e5", "d7d5", "e1e2"] {
+            let mv = game.parse_move(chess_move).unwrap();
+            game.make_move(mv.from, mv.to, mv.piece, mv.move_type)
+                .unwrap();
+        }
+        let fen = game.to_fen();
+        assert_eq!(
+            fen,
+            "rnbqkbnr/pp2pppp/8/2ppP3/8/8/PPPPKPPP/RNBQ1BNR b kq - 1 3"
+        );
+        assert_eq!(
+            Game::from_fen(&fen).unwrap().current_hash,
+            game.current_hash
+        );
+    }
+
+    #[test]
+    fn to_san_formats_moves() {
+        let san = |fen: &str, chess_move: &str| {
+            let game = Game::from_fen(fen).unwrap();
+            game.to_san(&game.parse_move(chess_move).unwrap())
         };
 
-        let move_data: Option<Vec<MoveData>> = moves.map(|moves_vec| {
-            moves_vec
-                .into_iter()
-                .map(|to| MoveData::new(from, to, piece, &self.board))
-                .collect()
-        });
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(san(start, "e2e4"), "e4");
+        assert_eq!(san(start, "g1f3"), "Nf3");
+
+        // knights on b1 and f3 both reach d2, rooks on a1 and a5 both reach a3
+        let fen = "4k3/8/8/R7/8/5N2/8/RN2K3 w - - 0 1";
+        assert_eq!(san(fen, "b1d2"), "Nbd2");
+        assert_eq!(san(fen, "a1a3"), "R1a3");
+        // queens on a1, a3 and c1 all reach b2
+        assert_eq!(san("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1", "a1b2"), "Qa1b2");
+
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(san(kiwipete, "e1g1"), "O-O");
+        assert_eq!(san(kiwipete, "e1c1"), "O-O-O");
+        assert_eq!(san(kiwipete, "d5e6"), "dxe6");
+        assert_eq!(san(kiwipete, "e5f7"), "Nxf7");
+        assert_eq!(san(kiwipete, "f3f6"), "Qxf6");
+
+        assert_eq!(san("8/8/8/3pP3/8/8/8/K6k w - d6 0 1", "e5d6"), "exd6");
+        assert_eq!(san("1n2k3/P7/8/8/8/8/8/K7 w - - 0 1", "a7b8q"), "axb8=Q+");
+        assert_eq!(san("6k1/5ppp/8/8/8/8/8/K3R3 w - - 0 1", "e1e8"), "Re8#");
+    }
+
+    #[test]
+    fn parse_move_accepts_san_and_long_algebraic() {
+        let game = Game::from_fen("r3k3/1P6/8/3pP3/8/8/8/R3K1N1 w Qq d6 0 1").unwrap();
+        let parse = |notation: &str| game.parse_move(notation).map(|mv| mv.to_uci());
+
+        for notation in ["Nf3", "Ng1-f3", "g1f3", "Ngf3", "Nf3+!"] {
+            assert_eq!(parse(notation), Ok("g1f3".to_string()), "{notation}");
+        }
+        for notation in ["exd6", "ed6", "e5xd6", "e5d6"] {
+            assert_eq!(parse(notation), Ok("e5d6".to_string()), "{notation}");
+        }
+        for notation in ["bxa8=Q+", "bxa8Q", "ba8q", "b7a8q", "b7xa8=Q"] {
+            assert_eq!(parse(notation), Ok("b7a8q".to_string()), "{notation}");
+        }
+        for notation in ["O-O-O", "0-0-0", "e1c1"] {
+            assert_eq!(parse(notation), Ok("e1c1".to_string()), "{notation}");
+        }
+
+        // coordinate moves are classified here and validated by `make_move`
+        let castle = game.parse_move("O-O").unwrap();
+        assert_eq!(
+            game.clone()
+                .make_move(castle.from, castle.to, castle.piece, castle.move_type),
+            Err(ChessError::InvalidCastle)
+        );
+        assert_eq!(parse("b8=Q"), Ok("b7b8q".to_string()));
+        assert_eq!(parse("b8"), Err(ChessError::InvalidPromotion));
+        assert_eq!(parse("Bb5"), Err(ChessError::InvalidMove));
+        assert_eq!(parse("Bg1f3"), Err(ChessError::InvalidMove));
+        assert_eq!(parse("e7e5"), Err(ChessError::InvalidMove));
+        assert_eq!(parse("Zz9"), Err(ChessError::InvalidNotation));
+        assert_eq!(parse("b7b8x"), Err(ChessError::InvalidNotation));
+    }
+
+    #[test]
+    fn clock_applies_increment_and_delays() {
+        let clock = |mode| {
+            let time_control = TimeControl {
+                start_time: TimeDelta::from_secs(60),
+                increment: TimeDelta::from_secs(5),
+                mode,
+            };
+            Clock::with_time_control(Timestamp::from(0), time_control, TimeDelta::from_secs(1))
+        };
+        let at = |secs: u64| Timestamp::from(secs * 1_000_000);
+
+        // White spends 3s, Black spends 8s
+        let mut fischer = clock(TimeMode::Fischer);
+        fischer.make_move(at(3), Color::White);
+        fischer.make_move(at(11), Color::Black);
+        assert_eq!(
+            fischer.time_left,
+            [TimeDelta::from_secs(62), TimeDelta::from_secs(57)]
+        );
+
+        for mode in [TimeMode::Bronstein, TimeMode::SimpleDelay] {
+            let mut delayed = clock(mode);
+            delayed.make_move(at(3), Color::White);
+            delayed.make_move(at(11), Color::Black);
+            assert_eq!(
+                delayed.time_left,
+                [TimeDelta::from_secs(60), TimeDelta::from_secs(57)]
+            );
+        }
+
+        // White has 60s, plus the 5s delay before its clock starts with a simple delay
+        let bronstein = clock(TimeMode::Bronstein);
+        assert!(!bronstein.timed_out(at(60), Color::White));
+        assert!(bronstein.timed_out(at(61), Color::White));
+        let simple = clock(TimeMode::SimpleDelay);
+        assert!(!simple.timed_out(at(65), Color::White));
+        assert!(simple.timed_out(at(66), Color::White));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let dead = [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2K3 b - - 0 1",
+            "4k3/4b3/8/8/8/8/8/2B1K3 w - - 0 1",
+        ];
+        for fen in dead {
+            assert!(
+                Game::from_fen(fen).unwrap().is_insufficient_material(),
+                "{fen}"
+            );
+        }
+
+        let alive = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "4k3/8/8/8/8/8/8/4K2R w - - 0 1",
+            "4k3/8/8/8/8/8/4p3/4K3 w - - 0 1",
+            "4k3/5b2/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1",
+            "4k3/4n3/8/8/8/8/8/1N2K3 w - - 0 1",
+        ];
+        for fen in alive {
+            assert!(
+                !Game::from_fen(fen).unwrap().is_insufficient_material(),
+                "{fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn color_preferences_assign_colors() {
+        let straight = [Color::White, Color::Black];
+        let swapped = [Color::Black, Color::White];
+        for coin in [false, true] {
+            assert_eq!(ColorPreference::White.assign(coin), straight);
+            assert_eq!(ColorPreference::Black.assign(coin), swapped);
+        }
+        assert_eq!(ColorPreference::Random.assign(false), straight);
+        assert_eq!(ColorPreference::Random.assign(true), swapped);
+
+        // the coin only depends on the players and the game chain, and lands both ways
+        let players = [PublicKey::test_key(1), PublicKey::test_key(2)];
+        let coins: Vec<bool> = (0..16)
+            .map(|index| ColorPreference::coin(players, ChainId::root(index)))
+            .collect();
+        assert!(coins.contains(&true) && coins.contains(&false));
+        assert_eq!(ColorPreference::coin(players, ChainId::root(3)), coins[3]);
+    }
+
+    #[test]
+    fn takeback_rewinds_moves() {
+        let mut game = Game::default().new();
+        let play = |game: &mut Game, chess_move: &str| {
+            let active = game.active;
+            let mv = game.parse_move(chess_move).unwrap();
+            let san = game.to_san(&mv);
+            game.make_move(mv.from, mv.to, mv.piece, mv.move_type)
+                .unwrap();
+            game.create_move_string(active, san);
+        };
 
-        move_data
+        play(&mut game, "e4");
+        let after_white = game.clone();
+        play(&mut game, "d5");
+        play(&mut game, "exd5");
+        let after_capture = game.clone();
+        play(&mut game, "Qxd5");
+
+        assert_eq!(game.takeback(5), Err(ChessError::InvalidRequest));
+        assert_eq!(game.takeback(0), Err(ChessError::InvalidRequest));
+        game.takeback(1).unwrap();
+        assert_eq!(game, after_capture);
+        game.takeback(2).unwrap();
+        assert_eq!(game, after_white);
+        game.takeback(1).unwrap();
+        assert_eq!(game, Game::default().new());
     }
 }
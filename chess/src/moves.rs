@@ -4,6 +4,7 @@ pub const NOT_A_FILE: Bitboard = 0xFEFEFEFEFEFEFEFE;
 pub const NOT_H_FILE: Bitboard = 0x7F7F7F7F7F7F7F7F;
 pub const NOT_HG_FILE: Bitboard = 0x3F3F3F3F3F3F3F3F;
 pub const NOT_AB_FILE: Bitboard = 0xFCFCFCFCFCFCFCFC;
+pub const LIGHT_SQUARES: Bitboard = 0x55AA55AA55AA55AA;
 
 /// A function to compute all possible pawn moves
 pub fn computed_pawn_moves(color: &Color) -> Vec<Bitboard> {
@@ -1,197 +1,225 @@
-// use crate::{chessboard::ChessBoard, piece::Piece, square::Square, ChessError, Game, MoveType};
-//
-// pub fn perft(depth: u32) -> (u32, u64, u64, u64) {
-//     let mut game = Game::new();
-//     perft_helper(&mut game, depth)
-// }
-//
-// fn perft_helper(game: &mut Game, depth: u32) -> (u32, u64, u64, u64) {
-//     if depth == 0 {
-//         return (depth, 1, 0, 0);
-//     }
-//
-//     let mut nodes = 0;
-//     let mut captures = 0;
-//     let mut moves_made = 0;
-//     let mut pieces = game.board.all_pieces();
-//
-//     while pieces != 0 {
-//         let from_square = Square::usize_to_square(pieces.trailing_zeros() as usize);
-//         let mut m = MoveType::Move;
-//         if let Some(piece) = game.board.get_piece_at(from_square) {
-//             if let Some(moves) = get_possible_moves(&game.board, from_square, piece) {
-//                 log::info!("{:?}: {:?}", piece, moves);
-//                 for to in moves {
-//                     // Save the current state for undoing
-//                     let captured_piece = game.board.get_piece_at(to);
-//                     if captured_piece.is_some() {
-//                         captures += 1;
-//                         m = MoveType::Capture(captured_piece.unwrap());
-//                     }
-//
-//                     let result = game.make_move(from_square, to, piece, m);
-//
-//                     match result {
-//                         Ok(_) => {
-//                             let (_d, n, _c, m) = perft_helper(game, depth - 1);
-//                             nodes += n;
-//                             moves_made += 1;
-//
-//                             game.board
-//                                 .undo_move(from_square, to, &piece, captured_piece)
-//                                 .unwrap();
-//                         }
-//                         Err(e) => {
-//                             if e == ChessError::InvalidCapture {
-//                                 // Handle invalid capture specifically
-//                                 // This assumes `captured_piece` needs to be adjusted
-//                                 captures -= 1;
-//                             } else {
-//                                 // Handle other errors or decrement moves_made if needed
-//                                 moves_made -= 1; // Adjust this if `moves_made` should reflect something else
-//                             }
-//                         }
-//                     }
-//                     break;
-//                 }
-//             }
-//         }
-//
-//         pieces &= pieces - 1;
-//     }
-//
-//     (depth, nodes, captures, moves_made)
-// }
-//
-// fn get_possible_moves(board: &ChessBoard, from: Square, piece: Piece) -> Option<Vec<Square>> {
-//     let color = piece.color();
-//     match piece {
-//         Piece::WhitePawn | Piece::BlackPawn => board.get_pawn_moves(from, color),
-//         Piece::WhiteKnight | Piece::BlackKnight => board.get_knight_moves(from, color),
-//         Piece::WhiteKing | Piece::BlackKing => board.get_king_moves(from, color),
-//         Piece::WhiteBishop | Piece::BlackBishop => board.get_bishop_moves(from, color),
-//         Piece::WhiteRook | Piece::BlackRook => board.get_rook_moves(from, color),
-//         Piece::WhiteQueen | Piece::BlackQueen => board.get_queen_moves(from, color),
-//     }
-// }
-
-use std::collections::HashSet;
-
-use crate::{
-    chessboard::ChessBoard, piece::Piece, square::Square, ChessError, Game, MoveData, MoveType,
-};
-
-pub fn perft(depth: u32, game: &mut Game) -> (u32, u64, u64, u64) {
-    perft_helper(game, depth)
+use std::ops::AddAssign;
+
+use crate::{Game, MoveData, MoveType};
+
+/// Leaf counts of a perft run, broken down the way reference engines report them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PerftStats {
+    pub nodes: u64,
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    pub checkmates: u64,
 }
 
-fn perft_helper(game: &mut Game, depth: u32) -> (u32, u64, u64, u64) {
+impl AddAssign for PerftStats {
+    fn add_assign(&mut self, other: Self) {
+        self.nodes += other.nodes;
+        self.captures += other.captures;
+        self.en_passants += other.en_passants;
+        self.castles += other.castles;
+        self.promotions += other.promotions;
+        self.checks += other.checks;
+        self.checkmates += other.checkmates;
+    }
+}
+
+/// Counts the leaf nodes of the legal move tree of `game` at `depth`, playing every move through
+/// `Game::make_move_unchecked` and `Game::unmake_move`
+pub fn perft(game: &mut Game, depth: u32) -> u64 {
     if depth == 0 {
-        return (depth, 1, 0, 0);
+        return 1;
+    }
+
+    let moves = game.board.legal_moves(game.active);
+    if depth == 1 {
+        return moves.len() as u64;
     }
 
     let mut nodes = 0;
-    let mut captures = 0;
-    let mut moves_made = 0;
-    let mut pieces = game.board.all_pieces();
-    let mut move_count: u8 = 0;
-    let mut undo_count: u8 = 0;
-
-    // while pieces != 0 {
-    //     let from_square = Square::usize_to_square(pieces.trailing_zeros() as usize);
-    //     if let Some(piece) = game.board.get_piece_at(from_square) {
-    //         if let Some(moves) = get_possible_moves(&game.board, from_square, piece) {
-    //             for mv in moves {
-    //                 let result =
-    //                     game.make_move(mv.from, mv.to, mv.piece, mv.move_type, &mut move_count);
-    //                 match result {
-    //                     Ok(_) => {
-    //                         let (_d, n, _c, _m) = perft_helper(game, depth - 1);
-    //                         nodes += n;
-    //                         moves_made += 1;
-
-    //                         game.board
-    //                             .undo_move(&mv, &mut undo_count)
-    //                             .expect("Undo failed");
-    //                     }
-    //                     Err(e) => {
-    //                         if e == ChessError::InvalidCapture {
-    //                             // Handle invalid capture specifically
-    //                             // This assumes `captured_piece` needs to be adjusted
-    //                             // captures -= 1;
-    //                         }
-    //                     }
-    //                 }
-    //             }
-    //         }
-    //     }
-
-    //     pieces &= pieces - 1;
-    // }
-
-    let possible_moves = generate_all_legal_moves(&game.board);
-
-    for move_data in possible_moves {
-        let result = game.make_move(
-            move_data.from,
-            move_data.to,
-            move_data.piece,
-            move_data.move_type,
-            &mut move_count,
-        );
-        match result {
-            Ok(_) => {
-                let (_d, n, c, m) = perft_helper(game, depth - 1);
-                nodes += n;
-                captures += c;
-                moves_made += m + 1;
-                log::info!("Move made: {:?}", move_data);
-
-                game.board
-                    .undo_move(&move_data, &mut undo_count)
-                    .expect("Undo failed");
-            }
-            Err(e) => {
-                log::warn!("Invalid move: {:?}", e);
-            }
-        }
+    for mv in moves {
+        game.make_move_unchecked(mv);
+        nodes += perft(game, depth - 1);
+        game.unmake_move().expect("Undo failed");
+    }
+    nodes
+}
+
+/// Returns the perft node count below every root move, the counts add up to `perft(game, depth)`
+pub fn perft_divide(game: &mut Game, depth: u32) -> Vec<(MoveData, u64)> {
+    let mut divide = Vec::new();
+    if depth == 0 {
+        return divide;
     }
 
-    log::info!("Moves count: {:?} Undo Moves: {:?}", move_count, undo_count);
+    for mv in game.board.legal_moves(game.active) {
+        game.make_move_unchecked(mv);
+        divide.push((mv, perft(game, depth - 1)));
+        game.unmake_move().expect("Undo failed");
+    }
+    divide
+}
 
-    (depth, nodes, captures, moves_made)
+/// Counts the leaf nodes at `depth` together with the captures, en passants, castles, promotions,
+/// checks and checkmates among the moves leading to them
+pub fn perft_stats(game: &mut Game, depth: u32) -> PerftStats {
+    let mut stats = PerftStats::default();
+    if depth == 0 {
+        stats.nodes = 1;
+        return stats;
+    }
+
+    for mv in game.board.legal_moves(game.active) {
+        stats += move_stats(game, mv, depth);
+    }
+    stats
 }
 
-fn get_possible_moves(board: &ChessBoard, from: Square, piece: Piece) -> Option<Vec<Square>> {
-    let color = piece.color();
-    let moves = match piece {
-        Piece::WhitePawn | Piece::BlackPawn => board.get_pawn_moves(from, color),
-        Piece::WhiteKnight | Piece::BlackKnight => board.get_knight_moves(from, color),
-        Piece::WhiteKing | Piece::BlackKing => board.get_king_moves(from, color),
-        Piece::WhiteBishop | Piece::BlackBishop => board.get_bishop_moves(from, color),
-        Piece::WhiteRook | Piece::BlackRook => board.get_rook_moves(from, color),
-        Piece::WhiteQueen | Piece::BlackQueen => board.get_queen_moves(from, color),
-    };
-
-    moves
+/// Returns the perft stats below every root move in a single walk, they add up to
+/// `perft_stats(game, depth)`
+pub fn perft_divide_stats(game: &mut Game, depth: u32) -> Vec<(MoveData, PerftStats)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+
+    game.board
+        .legal_moves(game.active)
+        .into_iter()
+        .map(|mv| (mv, move_stats(game, mv, depth)))
+        .collect()
 }
 
-/// Generate all possible Moves
-pub fn generate_all_legal_moves(board: &ChessBoard) -> Vec<MoveData> {
-    let mut all_moves: Vec<MoveData> = Vec::new();
-
-    for sq in 0..64 {
-        let from = Square::usize_to_square(sq);
-        if let Some(piece) = board.get_piece_at(from) {
-            if let Some(moves) = get_possible_moves(&board, from, piece) {
-                log::info!("Possible moves found at: {:?} for piece: {:?}", from, piece);
-                all_moves.extend(
-                    moves
-                        .into_iter()
-                        .map(|to| MoveData::new(from, to, piece, &board)),
-                );
+/// The perft stats of the subtree below a legal move `mv`, counting `mv` itself when `depth` is 1
+fn move_stats(game: &mut Game, mv: MoveData, depth: u32) -> PerftStats {
+    let captures = game.board.get_piece_at(mv.to).is_some();
+    game.make_move_unchecked(mv);
+
+    let mut stats = PerftStats::default();
+    if depth == 1 {
+        stats.nodes = 1;
+        match mv.move_type {
+            MoveType::EnPassant => {
+                stats.captures += 1;
+                stats.en_passants += 1;
             }
+            MoveType::Castle(_) => stats.castles += 1,
+            MoveType::Promotion(_) => stats.promotions += 1,
+            _ => {}
         }
+        if captures {
+            stats.captures += 1;
+        }
+        if game.board.in_check(game.active) {
+            stats.checks += 1;
+            if game.board.legal_moves(game.active).is_empty() {
+                stats.checkmates += 1;
+            }
+        }
+    } else {
+        stats = perft_stats(game, depth - 1);
+    }
+
+    game.unmake_move().expect("Undo failed");
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference positions from https://www.chessprogramming.org/Perft_Results
+    const POSITIONS: [(&str, &str, &[u64]); 6] = [
+        (
+            "startpos",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            &[20, 400, 8902, 197281],
+        ),
+        (
+            "kiwipete",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2039, 97862],
+        ),
+        (
+            "position 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812, 43238],
+        ),
+        (
+            "position 4",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467],
+        ),
+        (
+            "position 5",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1486, 62379],
+        ),
+        (
+            "position 6",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2079, 89890],
+        ),
+    ];
+
+    #[test]
+    fn reference_positions() {
+        for (name, fen, expected) in POSITIONS {
+            let mut game = Game::default().with_fen(fen);
+            let start = game.clone();
+
+            for (depth, &nodes) in expected.iter().enumerate() {
+                let depth = depth as u32 + 1;
+                assert_eq!(perft(&mut game, depth), nodes, "{name} at depth {depth}");
+            }
+            assert_eq!(game, start, "{name} was not restored");
+        }
+    }
+
+    #[test]
+    fn divide_adds_up_to_perft() {
+        let mut game = Game::default().with_fen(POSITIONS[1].1);
+        let divide = perft_divide(&mut game, 2);
+
+        assert_eq!(divide.len(), 48);
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2039);
+
+        let mut total = PerftStats::default();
+        for (_, stats) in perft_divide_stats(&mut game, 2) {
+            total += stats;
+        }
+        assert_eq!(total, perft_stats(&mut game, 2));
+    }
+
+    #[test]
+    fn stats_breakdown() {
+        let mut game = Game::default().with_fen(POSITIONS[0].1);
+        let stats = perft_stats(&mut game, 4);
+        assert_eq!(
+            stats,
+            PerftStats {
+                nodes: 197281,
+                captures: 1576,
+                checks: 469,
+                checkmates: 8,
+                ..Default::default()
+            }
+        );
+
+        let mut game = Game::default().with_fen(POSITIONS[1].1);
+        let stats = perft_stats(&mut game, 2);
+        assert_eq!(
+            stats,
+            PerftStats {
+                nodes: 2039,
+                captures: 351,
+                en_passants: 1,
+                castles: 91,
+                promotions: 0,
+                checks: 3,
+                checkmates: 0,
+            }
+        );
     }
-    all_moves
 }
@@ -0,0 +1,564 @@
+use std::iter::Peekable;
+
+use linera_sdk::base::{BcsHashable, CryptoHash, Timestamp};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{piece::Color, ChessError, FenError, Game, GameResult, Termination};
+
+/// The maximum length of a movetext line in exported PGN
+const LINE_LENGTH: usize = 80;
+
+/// A function to get the PGN result token of a game, `*` while it is in play
+pub fn result(game: &Game) -> &'static str {
+    game.result.map_or("*", |result| result.to_pgn())
+}
+
+/// A function to format a block timestamp as a PGN date (`YYYY.MM.DD`), `????.??.??` if unknown
+pub fn date(timestamp: Timestamp) -> String {
+    if timestamp == Timestamp::from(0) {
+        return "????.??.??".to_string();
+    }
+
+    // Civil date from days since the Unix epoch, in the proleptic Gregorian calendar
+    let days = (timestamp.micros() / 86_400_000_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+/// The text of an exported game, hashed to identify the game
+#[derive(Serialize, Deserialize)]
+struct PgnText(String);
+
+impl BcsHashable for PgnText {}
+
+/// A function to hash the text of an exported game
+pub fn hash(pgn: &str) -> CryptoHash {
+    CryptoHash::new(&PgnText(pgn.to_string()))
+}
+
+/// A function to render a game as PGN, with the given tag pairs followed by the SAN movetext
+pub fn write(tags: &[(&str, String)], game: &Game) -> String {
+    let mut pgn = String::new();
+    for (name, value) in tags {
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+        pgn.push_str(&format!("[{name} \"{value}\"]\n"));
+    }
+    pgn.push('\n');
+
+    // The last row holds the move just played, which is Black's if White is to move
+    let last_number = match game.active {
+        Color::White => game.fullmove_count.saturating_sub(1),
+        Color::Black => game.fullmove_count,
+    };
+    let first_number = (last_number as usize + 1)
+        .saturating_sub(game.moves.len())
+        .max(1);
+
+    let mut tokens = Vec::new();
+    for (i, row) in game.moves.iter().enumerate() {
+        let number = first_number + i;
+        match (&row.white, &row.black) {
+            (Some(white), black) => {
+                tokens.push(format!("{number}."));
+                tokens.push(white.clone());
+                tokens.extend(black.clone());
+            }
+            (None, Some(black)) => {
+                tokens.push(format!("{number}..."));
+                tokens.push(black.clone());
+            }
+            (None, None) => {}
+        }
+    }
+    tokens.push(result(game).to_string());
+
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.len() + 1 + token.len() > LINE_LENGTH {
+            pgn.push_str(&line);
+            pgn.push('\n');
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&token);
+    }
+    pgn.push_str(&line);
+    pgn.push('\n');
+    pgn
+}
+
+/// Errors reported when reading PGN
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+pub enum PgnError {
+    #[error("Invalid tag pair '{0}'")]
+    InvalidTag(String),
+    #[error("Unterminated comment")]
+    UnterminatedComment,
+    #[error("Invalid numeric annotation glyph")]
+    InvalidNag,
+    #[error("Unbalanced variation")]
+    UnbalancedVariation,
+    #[error("Invalid FEN tag: {0}")]
+    InvalidFen(#[from] FenError),
+    #[error("Illegal move '{notation}' at ply {ply}: {error}")]
+    IllegalMove {
+        ply: usize,
+        notation: String,
+        error: ChessError,
+    },
+}
+
+/// An alternative line to the move played at `ply`, counted from 0 at the starting position
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Variation {
+    pub ply: usize,
+    /// The moves of the line, in SAN
+    pub moves: Vec<String>,
+    /// Variations nested in this line, their `ply` also counts from the starting position
+    pub variations: Vec<Variation>,
+}
+
+/// A game read from PGN
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnGame {
+    /// The tag pairs, in the order they appear
+    pub tags: Vec<(String, String)>,
+    /// The game with every mainline move played
+    pub game: Game,
+    /// The result token closing the movetext, `*` if it is missing
+    pub result: String,
+    /// The variations of the mainline, empty unless they were kept
+    pub variations: Vec<Variation>,
+}
+
+impl PgnGame {
+    /// A function to get the value of a tag
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Move(String),
+    Nag,
+    OpenVariation,
+    CloseVariation,
+    Result(String),
+}
+
+/// A function to read every game of a PGN file, skipping variations
+pub fn read(pgn: &str) -> Result<Vec<PgnGame>, PgnError> {
+    read_with(pgn, false)
+}
+
+/// A function to read every game of a PGN file, keeping variations if `keep_variations` is set.
+/// Games start from the `FEN` tag when present and have their moves validated and recorded in SAN
+pub fn read_with(pgn: &str, keep_variations: bool) -> Result<Vec<PgnGame>, PgnError> {
+    let mut tokens = tokenize(pgn)?.into_iter().peekable();
+    let mut games = Vec::new();
+
+    while tokens.peek().is_some() {
+        let mut tags = Vec::new();
+        while let Some(Token::Tag(..)) = tokens.peek() {
+            if let Some(Token::Tag(name, value)) = tokens.next() {
+                tags.push((name, value));
+            }
+        }
+
+        let fen = tags
+            .iter()
+            .find(|(name, _)| name == "FEN")
+            .map(|(_, fen)| fen.as_str());
+        let mut game = match fen {
+            Some(fen) => Game::from_fen(fen)?,
+            None => Game::default().new(),
+        };
+
+        let mut mainline = Variation::default();
+        let result = read_line(
+            &mut game,
+            &mut tokens,
+            keep_variations,
+            &mut mainline,
+            false,
+        )?;
+        if game.is_checkmate() {
+            game.finish(GameResult::win(
+                game.active.opposite(),
+                Termination::Checkmate,
+            ));
+        } else if game.is_stalemate() {
+            game.finish(GameResult::draw(Termination::Stalemate));
+        }
+
+        games.push(PgnGame {
+            tags,
+            game,
+            result,
+            variations: mainline.variations,
+        });
+    }
+
+    Ok(games)
+}
+
+/// Plays the moves of a line until its result, or the end of the variation if `nested`
+fn read_line(
+    game: &mut Game,
+    tokens: &mut Peekable<impl Iterator<Item = Token>>,
+    keep_variations: bool,
+    line: &mut Variation,
+    nested: bool,
+) -> Result<String, PgnError> {
+    loop {
+        match tokens.peek() {
+            // A new game starting without a result closes this one
+            None | Some(Token::Tag(..)) if !nested => return Ok("*".to_string()),
+            None | Some(Token::Tag(..)) => return Err(PgnError::UnbalancedVariation),
+            _ => {}
+        }
+
+        match tokens.next() {
+            Some(Token::Move(notation)) => line.moves.push(play(game, &notation)?),
+            Some(Token::OpenVariation) => {
+                // A variation replaces the last move played
+                let mut branch = game.clone();
+                branch
+                    .unmake_move()
+                    .map_err(|_| PgnError::UnbalancedVariation)?;
+                let mut variation = Variation {
+                    ply: branch.history.len(),
+                    ..Variation::default()
+                };
+                read_line(&mut branch, tokens, keep_variations, &mut variation, true)?;
+                if keep_variations {
+                    line.variations.push(variation);
+                }
+            }
+            Some(Token::CloseVariation) if nested => return Ok(String::new()),
+            Some(Token::Result(result)) if !nested => return Ok(result),
+            Some(Token::CloseVariation | Token::Result(_)) => {
+                return Err(PgnError::UnbalancedVariation)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Plays a move and records it in the move table, returning its SAN
+fn play(game: &mut Game, notation: &str) -> Result<String, PgnError> {
+    let ply = game.history.len() + 1;
+    let illegal = |error| PgnError::IllegalMove {
+        ply,
+        notation: notation.to_string(),
+        error,
+    };
+
+    let active = game.active;
+    let mv = game.parse_move(notation).map_err(illegal)?;
+    let san = game.to_san(&mv);
+    game.make_move(mv.from, mv.to, mv.piece, mv.move_type)
+        .map_err(illegal)?;
+    game.create_move_string(active, san.clone());
+    Ok(san)
+}
+
+/// Splits PGN text into tag pairs and movetext tokens, dropping comments and escaped lines
+fn tokenize(pgn: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut chars = pgn.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            // Escaped lines and rest-of-line comments
+            '%' if line_start => {
+                chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+                line_start = true;
+                continue;
+            }
+            ';' => {
+                chars.by_ref().take_while(|&c| c != '\n').for_each(drop);
+                line_start = true;
+                continue;
+            }
+            '{' => {
+                if !chars.by_ref().any(|c| c == '}') {
+                    return Err(PgnError::UnterminatedComment);
+                }
+            }
+            '[' => tokens.push(read_tag(&mut chars)?),
+            '(' => tokens.push(Token::OpenVariation),
+            ')' => tokens.push(Token::CloseVariation),
+            '$' => {
+                let mut nag = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                    nag.push(c);
+                    chars.next();
+                }
+                if nag.is_empty() {
+                    return Err(PgnError::InvalidNag);
+                }
+                tokens.push(Token::Nag);
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut symbol = c.to_string();
+                while let Some(&c) = chars
+                    .peek()
+                    .filter(|c| !c.is_whitespace() && !"{}()[];$".contains(**c))
+                {
+                    symbol.push(c);
+                    chars.next();
+                }
+                tokens.extend(symbol_token(&symbol));
+            }
+        }
+        line_start = false;
+    }
+
+    Ok(tokens)
+}
+
+/// Reads a `[Name "value"]` tag pair after its opening bracket
+fn read_tag(chars: &mut Peekable<impl Iterator<Item = char>>) -> Result<Token, PgnError> {
+    let mut raw = String::from("[");
+    let mut name = String::new();
+    let mut name_done = false;
+    let mut value = None;
+
+    while let Some(c) = chars.next() {
+        raw.push(c);
+        match c {
+            ']' => break,
+            '"' if value.is_none() => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => text.extend(chars.next()),
+                        Some('"') => break,
+                        Some(c) => text.push(c),
+                        None => return Err(PgnError::InvalidTag(raw)),
+                    }
+                }
+                raw.push_str(&text);
+                raw.push('"');
+                value = Some(text);
+            }
+            c if c.is_whitespace() => name_done = !name.is_empty(),
+            c if value.is_none() && !name_done => name.push(c),
+            _ => return Err(PgnError::InvalidTag(raw)),
+        }
+    }
+
+    match value {
+        Some(value) if !name.is_empty() && raw.ends_with(']') => Ok(Token::Tag(name, value)),
+        _ => Err(PgnError::InvalidTag(raw)),
+    }
+}
+
+/// Classifies a movetext symbol, dropping move numbers (`12.`, `12...`) in front of moves
+fn symbol_token(symbol: &str) -> Option<Token> {
+    if matches!(symbol, "1-0" | "0-1" | "1/2-1/2" | "*") {
+        return Some(Token::Result(symbol.to_string()));
+    }
+    if symbol.starts_with("0-0") || symbol.starts_with("O-O") {
+        return Some(Token::Move(symbol.to_string()));
+    }
+
+    let notation = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+    let notation = if notation.len() < symbol.len() && notation.starts_with('.') {
+        notation.trim_start_matches('.')
+    } else {
+        symbol.trim_start_matches('.')
+    };
+    (!notation.is_empty()).then(|| Token::Move(notation.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for chess_move in moves {
+            let active = game.active;
+            let mv = game.parse_move(chess_move).unwrap();
+            let san = game.to_san(&mv);
+            game.make_move(mv.from, mv.to, mv.piece, mv.move_type)
+                .unwrap();
+            game.create_move_string(active, san);
+        }
+    }
+
+    #[test]
+    fn writes_tags_and_movetext() {
+        let mut game = Game::default().new();
+        play(&mut game, &["f3", "e5", "g4", "Qh4"]);
+        game.finish(GameResult::win(Color::Black, Termination::Checkmate));
+
+        let tags = [
+            ("Event", "Casual \"blitz\"".to_string()),
+            ("Result", result(&game).to_string()),
+        ];
+        assert_eq!(
+            write(&tags, &game),
+            "[Event \"Casual \\\"blitz\\\"\"]\n[Result \"0-1\"]\n\n1. f3 e5 2. g4 Qh4# 0-1\n"
+        );
+    }
+
+    #[test]
+    fn numbers_moves_from_the_starting_position() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 30").unwrap();
+        play(&mut game, &["Kd7", "e4", "Kc6"]);
+
+        assert_eq!(write(&[], &game), "\n30... Kd7 31. e4 Kc6 *\n");
+    }
+
+    #[test]
+    fn wraps_long_movetext() {
+        let mut game = Game::default().new();
+        let shuffle = ["Nf3", "Nf6", "Ng1", "Ng8"];
+        for _ in 0..5 {
+            play(&mut game, &shuffle);
+        }
+
+        let pgn = write(&[], &game);
+        assert!(pgn.lines().all(|line| line.len() <= LINE_LENGTH));
+        assert!(pgn.ends_with("10. Ng1 Ng8 *\n"));
+    }
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(date(Timestamp::from(0)), "????.??.??");
+        // 2024-02-29 12:00:00 UTC
+        assert_eq!(date(Timestamp::from(1_709_208_000_000_000)), "2024.02.29");
+        // 1999-12-31 23:59:59 UTC
+        assert_eq!(date(Timestamp::from(946_684_799_000_000)), "1999.12.31");
+    }
+
+    const GAMES: &str = r#"% exported by a test
+[Event "Opera \"game\""]
+[White "Morphy"]
+[Black "Duke Karl / Count Isouard"]
+[Result "1-0"]
+
+1.e4 e5 2. Nf3 d6 3. d4 Bg4 {This is a weak move already.} 4. dxe5 Bxf3 5. Qxf3
+dxe5 6. Bc4 Nf6 7. Qb3 $6 Qe7 8. Nc3 c6 9. Bg5 (9. Qxb7 Qb4 (9... Nbd7)) 9... b5
+10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 14. Rd1 Qe6 15. Bxd7+
+Nxd7 16. Qb8+ Nxb8 17. Rd8# 1-0
+
+[Event "Endgame"]
+[SetUp "1"]
+[FEN "4k3/P7/8/8/8/8/8/4K3 w - - 0 60"]
+
+60. a8Q+ ; rest of line comment
+60... Kd7 *
+"#;
+
+    #[test]
+    fn reads_multiple_games() {
+        let games = read(GAMES).unwrap();
+        assert_eq!(games.len(), 2);
+
+        let opera = &games[0];
+        assert_eq!(opera.tag("Event"), Some("Opera \"game\""));
+        assert_eq!(opera.tag("White"), Some("Morphy"));
+        assert_eq!(opera.result, "1-0");
+        assert_eq!(
+            opera.game.result,
+            Some(GameResult::win(Color::White, Termination::Checkmate))
+        );
+        assert_eq!(opera.game.history.len(), 33);
+        assert!(opera.variations.is_empty());
+        assert_eq!(
+            opera.game.to_fen(),
+            "1n1Rkb1r/p4ppp/4q3/4p1B1/4P3/8/PPP2PPP/2K5 b k - 1 17"
+        );
+
+        let endgame = &games[1];
+        assert_eq!(endgame.result, "*");
+        assert_eq!(endgame.game.to_fen(), "Q7/3k4/8/8/8/8/8/4K3 w - - 1 61");
+        assert_eq!(
+            write(&[], &endgame.game),
+            "\n60. a8=Q+ Kd7 *\n",
+            "moves are recorded in SAN"
+        );
+    }
+
+    #[test]
+    fn keeps_variations() {
+        let games = read_with(GAMES, true).unwrap();
+        assert_eq!(
+            games[0].variations,
+            vec![Variation {
+                ply: 16,
+                moves: vec!["Qxb7".to_string(), "Qb4".to_string()],
+                variations: vec![Variation {
+                    ply: 17,
+                    moves: vec!["Nbd7".to_string()],
+                    variations: vec![],
+                }],
+            }]
+        );
+    }
+
+    #[test]
+    fn round_trips_exported_games() {
+        let game = read(GAMES).unwrap().remove(0).game;
+        let tags = [("Result", result(&game).to_string())];
+
+        let reread = read(&write(&tags, &game)).unwrap().remove(0);
+        assert_eq!(reread.game.moves, game.moves);
+        assert_eq!(reread.game.current_hash, game.current_hash);
+    }
+
+    #[test]
+    fn reports_invalid_pgn() {
+        assert_eq!(
+            read("1. e4 e5 2. Ke3 *"),
+            Err(PgnError::IllegalMove {
+                ply: 3,
+                notation: "Ke3".to_string(),
+                error: ChessError::InvalidMove,
+            })
+        );
+        assert_eq!(read("1. e4 (1. d4 *"), Err(PgnError::UnbalancedVariation));
+        assert_eq!(read("1. e4 ) *"), Err(PgnError::UnbalancedVariation));
+        assert_eq!(read("( 1. e4 ) *"), Err(PgnError::UnbalancedVariation));
+        assert_eq!(read("1. e4 { open"), Err(PgnError::UnterminatedComment));
+        assert_eq!(read("1. e4 $ *"), Err(PgnError::InvalidNag));
+        assert_eq!(
+            read("[White Morphy]\n*"),
+            Err(PgnError::InvalidTag("[White M".to_string()))
+        );
+        assert!(matches!(
+            read("[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"]\n*"),
+            Err(PgnError::InvalidFen(_))
+        ));
+    }
+}
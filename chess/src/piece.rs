@@ -53,6 +53,48 @@ impl Piece {
         }
     }
 
+    /// A function to get the piece of a color from its algebraic letter (`P`, `N`, `B`, `R`, `Q`, `K`), case-insensitive
+    pub fn from_letter(letter: char, color: Color) -> Option<Piece> {
+        let piece = match (letter.to_ascii_uppercase(), color) {
+            ('P', Color::White) => Piece::WhitePawn,
+            ('N', Color::White) => Piece::WhiteKnight,
+            ('B', Color::White) => Piece::WhiteBishop,
+            ('R', Color::White) => Piece::WhiteRook,
+            ('Q', Color::White) => Piece::WhiteQueen,
+            ('K', Color::White) => Piece::WhiteKing,
+            ('P', Color::Black) => Piece::BlackPawn,
+            ('N', Color::Black) => Piece::BlackKnight,
+            ('B', Color::Black) => Piece::BlackBishop,
+            ('R', Color::Black) => Piece::BlackRook,
+            ('Q', Color::Black) => Piece::BlackQueen,
+            ('K', Color::Black) => Piece::BlackKing,
+            _ => return None,
+        };
+        Some(piece)
+    }
+
+    /// A function to get the uppercase algebraic letter of a piece (`P`, `N`, `B`, `R`, `Q`, `K`)
+    pub fn letter(&self) -> char {
+        match self {
+            Piece::WhitePawn | Piece::BlackPawn => 'P',
+            Piece::WhiteKnight | Piece::BlackKnight => 'N',
+            Piece::WhiteBishop | Piece::BlackBishop => 'B',
+            Piece::WhiteRook | Piece::BlackRook => 'R',
+            Piece::WhiteQueen | Piece::BlackQueen => 'Q',
+            Piece::WhiteKing | Piece::BlackKing => 'K',
+        }
+    }
+
+    /// Returns true if the piece is a pawn
+    pub fn is_pawn(&self) -> bool {
+        matches!(self, Piece::WhitePawn | Piece::BlackPawn)
+    }
+
+    /// Returns true if the piece is a king
+    pub fn is_king(&self) -> bool {
+        matches!(self, Piece::WhiteKing | Piece::BlackKing)
+    }
+
     pub fn index(&self) -> usize {
         match self {
             Piece::WhitePawn => 0,
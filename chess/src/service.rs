@@ -8,7 +8,8 @@ use self::state::Chess;
 use async_graphql::{EmptySubscription, Object, Request, Response, Schema, SimpleObject};
 use chess::{
     piece::{Color, Piece},
-    Clock, GameState, Move, Operation, PlayerStats, PlayerTime,
+    Challenge, Clock, GameResult, GameState, Move, Operation, PlayerStats, PlayerTime, Seek,
+    TakebackRequest,
 };
 
 use linera_sdk::{
@@ -51,12 +52,16 @@ impl Service for ChessService {
 
 #[derive(Deserialize, Serialize, SimpleObject)]
 struct GameData {
-    board: String,         // ChessBoard
-    player_turn: Color,    // player's color to move
-    player: Color,         // players color
-    moves: Vec<Move>,      // moves made till now
-    opponent: Owner,       // opponent player id(Owner)
-    game_state: GameState, // State of the Game, Play, StaleMate or CheckMate
+    board: String,                             // ChessBoard
+    player_turn: Color,                        // player's color to move
+    player: Color,                             // players color
+    moves: Vec<Move>,                          // moves made till now
+    opponent: Owner,                           // opponent player id(Owner)
+    game_state: GameState,                     // State of the Game, Play, StaleMate or CheckMate
+    king_in_check: Option<Color>,              // color of the king in check, if any
+    draw_offer: Option<Color>,                 // color of the player offering a draw, if any
+    takeback_request: Option<TakebackRequest>, // pending takeback request, if any
+    result: Option<GameResult>,                // winner and termination reason, once finished
 }
 
 #[Object]
@@ -64,16 +69,16 @@ impl ChessService {
     async fn game_data(&self, player: Owner) -> GameData {
         let game = self.state.board.get();
         GameData {
-            board: game.board.to_fen(
-                &game.active_player(),
-                &game.halfmove_clock,
-                &game.fullmove_count,
-            ),
+            board: game.to_fen(),
             player_turn: game.active, // (todo!, to be removed)
             player: self.state.owners.get(&player).await.unwrap().unwrap(),
             moves: game.moves.clone(),
             opponent: self.state.opponent(player).unwrap(),
             game_state: game.state,
+            king_in_check: game.board.in_check(game.active).then_some(game.active),
+            draw_offer: *self.state.draw_offer.get(),
+            takeback_request: *self.state.takeback_request.get(),
+            result: game.result,
         }
     }
     async fn captured_pieces(&self) -> &Vec<Piece> {
@@ -88,4 +93,16 @@ impl ChessService {
     async fn get_leaderboard(&self) -> Vec<PlayerStats> {
         self.state.get_leaderboard()
     }
+    /// The seeks waiting for an opponent in the lobby
+    async fn open_seeks(&self) -> Vec<Seek> {
+        self.state.open_seeks().await
+    }
+    /// The open challenges sent or received on this chain
+    async fn open_challenges(&self) -> Vec<Challenge> {
+        self.state.open_challenges().await
+    }
+    /// The current game as PGN, with the Seven Tag Roster and the time control
+    async fn pgn(&self) -> String {
+        self.state.pgn().await
+    }
 }
@@ -74,6 +74,11 @@ impl Square {
         ((*self as usize / 8) as u8) + 1
     }
 
+    /// Returns the file letter of the square ('a'..='h')
+    pub const fn file(&self) -> char {
+        (b'a' + (*self as u8 % 8)) as char
+    }
+
     #[rustfmt::skip]
     pub const fn usize_to_square(i: usize) -> Self {
         match i {
@@ -1,8 +1,10 @@
 use std::collections::BTreeSet;
 
-use chess::{piece::Color, Clock, Game, GameChain, PlayerStats};
+use chess::{
+    pgn, piece::Color, Challenge, Clock, Game, GameChain, PlayerStats, Seek, TakebackRequest,
+};
 use linera_sdk::{
-    base::{Amount, Owner, PublicKey},
+    base::{Amount, ChainId, Owner, PublicKey, Timestamp},
     views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext},
 };
 
@@ -16,14 +18,32 @@ pub struct Chess {
     pub board: RegisterView<Game>,
     /// The current game clock
     pub clock: RegisterView<Clock>,
+    /// The player with a pending draw offer
+    pub draw_offer: RegisterView<Option<Color>>,
+    /// The pending request to take back moves
+    pub takeback_request: RegisterView<Option<TakebackRequest>>,
+    /// The block time at which the current game started
+    pub started_at: RegisterView<Timestamp>,
     /// The current game players
     pub players: RegisterView<Vec<Owner>>,
+    /// The public keys of the players, on a temporary game chain
+    pub player_keys: RegisterView<Vec<PublicKey>>,
     /// LeaderBoard (max 10)
     pub leaderboard: RegisterView<Vec<PlayerStats>>,
-    /// Player Stats
-    pub stats: RegisterView<PlayerStats>,
+    /// Player Stats, by player
+    pub stats: MapView<Owner, PlayerStats>,
     /// Temporary chains for individual games, by player.
     pub game_chains: MapView<PublicKey, BTreeSet<GameChain>>,
+    /// Open seeks in the lobby of the main chain, by ID
+    pub seeks: MapView<u64, Seek>,
+    /// The ID of the next seek
+    pub next_seek_id: RegisterView<u64>,
+    /// Open challenges sent or received by the players of this chain, by ID
+    pub challenges: MapView<u64, Challenge>,
+    /// The challenges waiting for an answer, kept by the main chain
+    pub pending_challenges: MapView<u64, Challenge>,
+    /// The ID of the next challenge
+    pub next_challenge_id: RegisterView<u64>,
     /// store the betting amount on temp chain.
     pub bet_amount: RegisterView<Amount>,
 }
@@ -49,9 +69,60 @@ impl Chess {
 
         players.iter().find(|&p| *p != player).cloned()
     }
-    /// A function to create and update player stats
-    pub fn player_stats(&mut self, player_stats: PlayerStats) {
-        self.stats.set(player_stats);
+    /// A function to get the player playing the given color
+    pub async fn player_with_color(&self, color: Color) -> Option<Owner> {
+        for player in self.get_players() {
+            if self.owners.get(player).await.unwrap() == Some(color) {
+                return Some(*player);
+            }
+        }
+        None
+    }
+
+    /// A function to get the open seeks of the lobby
+    pub async fn open_seeks(&self) -> Vec<Seek> {
+        let mut seeks = vec![];
+        self.seeks
+            .for_each_index_value(|_, seek| {
+                seeks.push(seek);
+                Ok(())
+            })
+            .await
+            .unwrap();
+        seeks
+    }
+
+    /// A function to get the open challenges of this chain
+    pub async fn open_challenges(&self) -> Vec<Challenge> {
+        let mut challenges = vec![];
+        self.challenges
+            .for_each_index_value(|_, challenge| {
+                challenges.push(challenge);
+                Ok(())
+            })
+            .await
+            .unwrap();
+        challenges
+    }
+
+    /// A function to forget a finished game chain of a player
+    pub async fn remove_game_chain(&mut self, player: &PublicKey, chain_id: ChainId) {
+        let Some(game_chains) = self.game_chains.get_mut(player).await.unwrap() else {
+            return;
+        };
+        game_chains.retain(|game| game.chain_id != chain_id);
+        if game_chains.is_empty() {
+            self.game_chains.remove(player).unwrap();
+        }
+    }
+
+    /// A function to get the stats of a player, empty if the player has not played yet
+    pub async fn player_stats(&self, player: &Owner) -> PlayerStats {
+        self.stats
+            .get(player)
+            .await
+            .unwrap()
+            .unwrap_or_else(|| PlayerStats::new(player.to_string()))
     }
 
     /// A function to get the leaderboard
@@ -68,12 +139,33 @@ impl Chess {
             .clone()
     }
 
-    /// A function to add the player stats to the leaderboard
+    /// A function to add the player stats to the leaderboard, replacing the previous stats of the
+    /// player and keeping the 10 players with the most wins
     pub fn add_player_leaderboard(&mut self, player: PlayerStats) {
         let leaderboard = self.leaderboard.get_mut();
-        if leaderboard.len() > 10 {
-            leaderboard.pop();
-        }
+        leaderboard.retain(|stats| stats.player_id != player.player_id);
         leaderboard.push(player);
+        leaderboard.sort_by(|a, b| b.wins.cmp(&a.wins));
+        leaderboard.truncate(10);
+    }
+
+    /// The current game as PGN, with the Seven Tag Roster and the time control
+    pub async fn pgn(&self) -> String {
+        let game = self.board.get();
+        let white = self.player_with_color(Color::White).await;
+        let black = self.player_with_color(Color::Black).await;
+        let name = |player: Option<Owner>| player.map_or("?".to_string(), |p| p.to_string());
+
+        let tags = [
+            ("Event", "MicroChess".to_string()),
+            ("Site", "Linera".to_string()),
+            ("Date", pgn::date(*self.started_at.get())),
+            ("Round", "-".to_string()),
+            ("White", name(white)),
+            ("Black", name(black)),
+            ("Result", pgn::result(game).to_string()),
+            ("TimeControl", self.clock.get().time_control.to_pgn()),
+        ];
+        pgn::write(&tags, game)
     }
 }
@@ -1,8 +1,4 @@
-use crate::{
-    piece::{Color, Piece},
-    prng::*,
-    square::Square,
-};
+use crate::{piece::Piece, prng::*, square::Square};
 use lazy_static::lazy_static;
 
 lazy_static! {
@@ -52,9 +48,7 @@ pub fn update_castle_hash(new_rights: [bool; 4], hash: &mut u64) {
     }
 }
 
-// A function to update active_player hashing
-pub fn update_side_hash(side: Color, hash: &mut u64) {
-    if side == Color::White {
-        *hash ^= *BLACK_TO_MOVE;
-    }
+// A function to update active_player hashing, called on every change of turn
+pub fn update_side_hash(hash: &mut u64) {
+    *hash ^= *BLACK_TO_MOVE;
 }
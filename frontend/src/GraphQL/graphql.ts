@@ -58,6 +58,7 @@ export type GameData = {
   __typename?: 'GameData';
   board: Scalars['String']['output'];
   gameState: GameState;
+  kingInCheck?: Maybe<Color>;
   moves: Array<Move>;
   opponent: Scalars['Owner']['output'];
   player: Color;
@@ -79,40 +80,20 @@ export type Move = {
 
 export type OperationMutationRoot = {
   __typename?: 'OperationMutationRoot';
-  capturePiece: Array<Scalars['Int']['output']>;
-  makeMove: Array<Scalars['Int']['output']>;
   newGame: Array<Scalars['Int']['output']>;
-  pawnPromotion: Array<Scalars['Int']['output']>;
+  playMove: Array<Scalars['Int']['output']>;
   resign: Array<Scalars['Int']['output']>;
   startGame: Array<Scalars['Int']['output']>;
 };
 
 
-export type OperationMutationRootCapturePieceArgs = {
-  capturedPiece: Scalars['String']['input'];
-  from: Scalars['String']['input'];
-  piece: Scalars['String']['input'];
-  to: Scalars['String']['input'];
-};
-
-
-export type OperationMutationRootMakeMoveArgs = {
-  from: Scalars['String']['input'];
-  piece: Scalars['String']['input'];
-  to: Scalars['String']['input'];
-};
-
-
 export type OperationMutationRootNewGameArgs = {
   player: Scalars['Owner']['input'];
 };
 
 
-export type OperationMutationRootPawnPromotionArgs = {
-  from: Scalars['String']['input'];
-  piece: Scalars['String']['input'];
-  promotedPiece: Scalars['String']['input'];
-  to: Scalars['String']['input'];
+export type OperationMutationRootPlayMoveArgs = {
+  chessMove: Scalars['String']['input'];
 };
 
 
@@ -11,6 +11,7 @@ export const GAME_DATA = gql`
     gameData(player: $player) {
       board
       gameState
+      kingInCheck
       moves {
         black
         white
@@ -27,40 +28,9 @@ export const GET_BOARD = gql`
     board
   }
 `;
-export const PROMOTE_PIECE = gql`
-  mutation PromotePiece(
-    $from: String!
-    $to: String!
-    $piece: String!
-    $promotedPiece: String!
-  ) {
-    pawnPromotion(
-      from: $from
-      to: $to
-      piece: $piece
-      promotedPiece: $promotedPiece
-    )
-  }
-`;
-export const MOVE_PIECE = gql`
-  mutation MakeMove($from: String!, $to: String!, $piece: String!) {
-    makeMove(from: $from, to: $to, piece: $piece)
-  }
-`;
-
-export const CAPTURE_PIECE = gql`
-  mutation CapturePiece(
-    $from: String!
-    $to: String!
-    $piece: String!
-    $capturedPiece: String!
-  ) {
-    capturePiece(
-      from: $from
-      to: $to
-      piece: $piece
-      capturedPiece: $capturedPiece
-    )
+export const PLAY_MOVE = gql`
+  mutation PlayMove($chessMove: String!) {
+    playMove(chessMove: $chessMove)
   }
 `;
 
@@ -13,7 +13,7 @@ import blackQueen from '../../assets/new_assets/bq.png'
 import blackKing from '../../assets/new_assets/bk.png'
 import React from 'react'
 import { useMutation } from '@apollo/client'
-import { CAPTURE_PIECE, MOVE_PIECE } from '../../GraphQL/queries'
+import { PLAY_MOVE } from '../../GraphQL/queries'
 import generatePossibleMoves from './GeneratePossibleMoves'
 import { BoardType, Color, Piece, Square, SquareToPieceMap } from './types'
 import { useChess } from '../../context/ChessProvider'
@@ -74,8 +74,7 @@ export default function Board({
     en_passant,
   } = boardData
 
-  const [moveMutation] = useMutation(MOVE_PIECE)
-  const [captureMutation] = useMutation(CAPTURE_PIECE)
+  const [playMoveMutation] = useMutation(PLAY_MOVE)
 
   function getKingPosition(board: SquareToPieceMap) {
     for (const [square, piece] of Object.entries(board)) {
@@ -93,36 +92,12 @@ export default function Board({
     return parseInt(square.charAt(1))
   }
 
-  const capturePiece = async (
-    from: string,
-    to: string,
-    piece: string,
-    capturedPiece: string
-  ) => {
-    const tempBoard = boardData
-    captureMutation({
-      variables: {
-        piece,
-        from: from,
-        to: to,
-        endpoint: 'chess',
-        capturedPiece: capturedPiece,
-      },
-      onError: (error) => {
-        console.error('Message:', error.message)
-        // need to update the board State
-        setBoard(tempBoard)
-      },
-    })
-  }
-
-  const movePiece = async (from: string, to: string, piece: string) => {
+  // the contract derives the piece, capture, castle and en passant from the board
+  const playMove = async (from: string, to: string) => {
     const tempBoard = boardData
-    moveMutation({
+    playMoveMutation({
       variables: {
-        piece: piece,
-        from: from,
-        to: to,
+        chessMove: `${from}${to}`,
         endpoint: 'chess',
       },
       onError: (error) => {
@@ -173,7 +148,7 @@ export default function Board({
         }
 
         if (capturedPiece) {
-          await capturePiece(selectedSquare, to_square, piece, capturedPiece)
+          await playMove(selectedSquare, to_square)
           // used to make a capture on the local board
           setBoard((prevBoard: BoardType) => ({
             ...prevBoard,
@@ -185,11 +160,7 @@ export default function Board({
             },
           }))
         } else {
-          await movePiece(
-            selectedSquare,
-            to_square,
-            chessSettings.dragNdrop ? piece : (selectedPiece as Piece)
-          )
+          await playMove(selectedSquare, to_square)
           // used to make a move on the local board
           setBoard((prevBoard: BoardType) => ({
             ...prevBoard,
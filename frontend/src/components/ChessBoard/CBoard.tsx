@@ -36,14 +36,12 @@ function fenToPieceCode(piece: any) {
 // we need to return castling rights as well
 function fenToObj(fen: string): {
   position: SquareToPieceMap;
-  KingInCheck: string | null;
 } {
   // cut off any move, castling, etc info from the end
   // we're only interested in position information
   const FEN = fen.replace(/ .+$/, "");
   const rows = FEN.split("/");
   const position: any = {};
-  const check = fen.split(";");
   const castling = fen.split("");
 
   let currentRow = 8;
@@ -67,17 +65,8 @@ function fenToObj(fen: string): {
 
     currentRow = currentRow - 1;
   }
-  // The last part contains the check status
-  let KingInCheck: string | null = null;
-
-  if (check.length > 1) {
-    KingInCheck = check[1].trim();
-    KingInCheck; // 'bK' or any other status
-  }
-
   return {
     position,
-    KingInCheck,
   };
 }
 
@@ -92,6 +81,7 @@ const CBoard = () => {
   const [play] = useMutation(NEW_GAME);
   const [whiteTime, setWhiteTime] = React.useState(0); // 15 minutes
   const [blackTime, setBlackTime] = React.useState(0); // 15 minutes
+  const [kingInCheck, setKingInCheck] = React.useState<string | null>(null);
 
   const [gameData, { called: callGameData }] = useLazyQuery(GAME_DATA, {
     variables: {
@@ -105,6 +95,14 @@ const CBoard = () => {
       setColor(data.gameData.player);
       setMoves(data.gameData.moves);
       setOpponentId(data.gameData.opponent);
+      // 'wK' or 'bK' when a king is in check
+      setKingInCheck(
+        data.gameData.kingInCheck === "WHITE"
+          ? "wK"
+          : data.gameData.kingInCheck === "BLACK"
+            ? "bK"
+            : null,
+      );
     },
     onError: (error) => {
       console.log("Error: ", error);
@@ -166,7 +164,7 @@ const CBoard = () => {
     let obj = fenToObj(boardState);
     return {
       position: obj.position,
-      KingInCheck: obj.KingInCheck,
+      KingInCheck: kingInCheck,
       whiteCastle: false,
       blackCastle: false,
       en_passant: "e3",
@@ -178,12 +176,12 @@ const CBoard = () => {
     let obj = fenToObj(boardState);
     setBoard({
       position: obj.position,
-      KingInCheck: obj.KingInCheck,
+      KingInCheck: kingInCheck,
       whiteCastle: false,
       blackCastle: false,
       en_passant: "e3",
     });
-  }, [boardState]);
+  }, [boardState, kingInCheck]);
 
   const [moves, setMoves] = React.useState<
     Array<{ white: string; black: string }>
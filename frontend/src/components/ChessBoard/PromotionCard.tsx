@@ -8,7 +8,7 @@ import blackKnight from '../../assets/new_assets/bn.png'
 import blackBishop from '../../assets/new_assets/bb.png'
 import blackQueen from '../../assets/new_assets/bq.png'
 import { useMutation } from '@apollo/client'
-import { PROMOTE_PIECE } from '../../GraphQL/queries'
+import { PLAY_MOVE } from '../../GraphQL/queries'
 
 interface PromotionCardProps {
   color: 'white' | 'black'
@@ -52,7 +52,7 @@ export const PromotionCard = ({
   promoteData,
   setPromoteData,
 }: PromotionCardProps) => {
-  const [promoteMutation] = useMutation(PROMOTE_PIECE)
+  const [playMoveMutation] = useMutation(PLAY_MOVE)
 
   const pieceData = color === 'white' ? whitePieces : blackPieces
 
@@ -61,12 +61,11 @@ export const PromotionCard = ({
     promoteData: { from: string; to: string; piece: string; show: boolean }
   ) => {
     console.log('Promote Piece:', promoteData, piece)
-    await promoteMutation({
+    // UCI promotion, e.g. `e7e8q`
+    const promotion = piece.charAt(1).toLowerCase()
+    await playMoveMutation({
       variables: {
-        from: promoteData.from,
-        to: promoteData.to,
-        piece: promoteData.piece,
-        promotedPiece: piece,
+        chessMove: `${promoteData.from}${promoteData.to}${promotion}`,
         endpoint: 'chess',
       },
       onError: (error: any) => {
@@ -43,8 +43,11 @@ APP_ID=$(linera -w0 --wait-for-outgoing-messages \
   project publish-and-create chess chess $CHESS_CHAIN \
     --json-argument "{
         \"players\": [\"$OWNER_1\", \"$OWNER_2\"],
-        \"startTime\": 600000000,
-        \"increment\": 600000000,
+        \"timeControl\": {
+            \"startTime\": 900000000,
+            \"increment\": 0,
+            \"mode\": \"Fischer\"
+        },
         \"blockDelay\": 100000000
     }")
 